@@ -0,0 +1,569 @@
+use crate::db::{BLOCK_BODY_TABLE, ETH_TX_TABLE, HEADERS_TABLE, SENDERS_TABLE};
+use anyhow::{bail, Result};
+use ethers::types::{Address, H256, U256};
+use ethers::utils::keccak256;
+use ethers::utils::rlp::{Decodable, DecoderError, Rlp};
+use libmdbx::{Cursor, NoWriteMap, Transaction, RO};
+use serde::Serialize;
+
+// Typed transaction envelopes, first byte of stored tx
+pub const ACCESS_LIST_TX_TYPE: u8 = 1;
+pub const DYNAMIC_FEE_TX_TYPE: u8 = 2;
+pub const BLOB_TX_TYPE: u8 = 3;
+pub const SET_CODE_TX_TYPE: u8 = 4;
+
+// Block header, fields added by forks are None before fork
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Header {
+    pub parent_hash: H256,
+    pub miner: Address,
+    pub state_root: H256,
+    pub difficulty: U256,
+    pub number: u64,
+    pub gas_limit: u64,
+    pub gas_used: u64,
+    pub timestamp: u64,
+    pub extra_data: Vec<u8>,
+    // London
+    pub base_fee_per_gas: Option<U256>,
+    // Shanghai
+    pub withdrawals_root: Option<H256>,
+    // Cancun
+    pub blob_gas_used: Option<u64>,
+    pub excess_blob_gas: Option<u64>,
+    pub parent_beacon_block_root: Option<H256>,
+}
+
+impl Header {
+    pub fn decode(data: &[u8]) -> Result<Self> {
+        let rlp = Rlp::new(data);
+        Ok(Header {
+            parent_hash: rlp.val_at(0)?,
+            miner: rlp.val_at(2)?,
+            state_root: rlp.val_at(3)?,
+            difficulty: rlp.val_at(7)?,
+            number: rlp.val_at(8)?,
+            gas_limit: rlp.val_at(9)?,
+            gas_used: rlp.val_at(10)?,
+            timestamp: rlp.val_at(11)?,
+            extra_data: rlp.val_at(12)?,
+            base_fee_per_gas: optional_at(&rlp, 15)?,
+            withdrawals_root: optional_at(&rlp, 16)?,
+            blob_gas_used: optional_at(&rlp, 17)?,
+            excess_blob_gas: optional_at(&rlp, 18)?,
+            parent_beacon_block_root: optional_at(&rlp, 19)?,
+        })
+    }
+}
+
+// Erigon BlockBody value: txs stored in BlockTransaction by sequential id
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BodyForStorage {
+    // id of system tx before block txs
+    pub base_tx_id: u64,
+    // block txs and 2 system txs
+    pub tx_amount: u32,
+    // empty before Shanghai
+    pub withdrawals: Vec<Withdrawal>,
+}
+
+// Beacon chain withdrawal, EIP-4895
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Withdrawal {
+    pub index: u64,
+    pub validator_index: u64,
+    pub address: Address,
+    pub amount_gwei: u64,
+}
+
+impl Decodable for Withdrawal {
+    fn decode(rlp: &Rlp) -> Result<Self, DecoderError> {
+        Ok(Withdrawal {
+            index: rlp.val_at(0)?,
+            validator_index: rlp.val_at(1)?,
+            address: rlp.val_at(2)?,
+            amount_gwei: rlp.val_at(3)?,
+        })
+    }
+}
+
+impl BodyForStorage {
+    pub fn decode(data: &[u8]) -> Result<Self> {
+        let rlp = Rlp::new(data);
+        Ok(BodyForStorage {
+            base_tx_id: rlp.val_at(0)?,
+            tx_amount: rlp.val_at(1)?,
+            // after uncles
+            withdrawals: if rlp.item_count()? > 3 {
+                rlp.list_at(3)?
+            } else {
+                Vec::new()
+            },
+        })
+    }
+
+    // Id of first block tx and txs count, system txs excluded
+    pub fn tx_range(&self) -> (u64, u32) {
+        (self.base_tx_id + 1, self.tx_amount.saturating_sub(2))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AccessListItem {
+    pub address: Address,
+    pub storage_keys: Vec<H256>,
+}
+
+// Signed EIP-7702 delegation of authority account to code of address
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Authorization {
+    pub chain_id: U256,
+    pub address: Address,
+    pub nonce: u64,
+    pub y_parity: u8,
+    pub r: U256,
+    pub s: U256,
+}
+
+impl Decodable for Authorization {
+    fn decode(rlp: &Rlp) -> Result<Self, DecoderError> {
+        Ok(Authorization {
+            chain_id: rlp.val_at(0)?,
+            address: rlp.val_at(1)?,
+            nonce: rlp.val_at(2)?,
+            y_parity: rlp.val_at(3)?,
+            r: rlp.val_at(4)?,
+            s: rlp.val_at(5)?,
+        })
+    }
+}
+
+// Signed tx of any envelope, fields not in envelope are None
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EthTx {
+    pub hash: H256,
+    pub tx_type: u8,
+    pub chain_id: Option<u64>,
+    pub nonce: u64,
+    pub gas_price: Option<U256>,
+    pub max_priority_fee_per_gas: Option<U256>,
+    pub max_fee_per_gas: Option<U256>,
+    pub gas: u64,
+    // None: contract creation
+    pub to: Option<Address>,
+    pub value: U256,
+    pub input: Vec<u8>,
+    pub access_list: Vec<AccessListItem>,
+    pub max_fee_per_blob_gas: Option<U256>,
+    pub blob_versioned_hashes: Vec<H256>,
+    pub authorization_list: Vec<Authorization>,
+}
+
+impl EthTx {
+    // Legacy RLP list or type byte followed by RLP payload, as stored in BlockTransaction
+    pub fn decode(data: &[u8]) -> Result<Self> {
+        let Some(first) = data.first() else {
+            bail!("Empty transaction");
+        };
+        let hash = H256::from(keccak256(data));
+        // legacy tx is RLP list, list prefix >= 0xc0
+        if *first >= 0xc0 {
+            let rlp = Rlp::new(data);
+            let v: u64 = rlp.val_at(6)?;
+            return Ok(EthTx {
+                hash,
+                tx_type: 0,
+                // EIP-155: v = chain_id * 2 + 35/36
+                chain_id: (v >= 35).then(|| (v - 35) / 2),
+                nonce: rlp.val_at(0)?,
+                gas_price: Some(rlp.val_at(1)?),
+                max_priority_fee_per_gas: None,
+                max_fee_per_gas: None,
+                gas: rlp.val_at(2)?,
+                to: decode_to(&rlp.at(3)?)?,
+                value: rlp.val_at(4)?,
+                input: rlp.val_at(5)?,
+                access_list: Vec::new(),
+                max_fee_per_blob_gas: None,
+                blob_versioned_hashes: Vec::new(),
+                authorization_list: Vec::new(),
+            });
+        }
+        let tx_type = *first;
+        let rlp = Rlp::new(&data[1..]);
+        // fee fields after nonce
+        let (gas_price, max_priority_fee_per_gas, max_fee_per_gas, fee_fields) = match tx_type {
+            ACCESS_LIST_TX_TYPE => (Some(rlp.val_at(2)?), None, None, 1),
+            DYNAMIC_FEE_TX_TYPE | BLOB_TX_TYPE | SET_CODE_TX_TYPE => {
+                (None, Some(rlp.val_at(2)?), Some(rlp.val_at(3)?), 2)
+            }
+            _ => bail!("Unknown transaction type {}", tx_type),
+        };
+        let i = 2 + fee_fields;
+        let (max_fee_per_blob_gas, blob_versioned_hashes) = if tx_type == BLOB_TX_TYPE {
+            (Some(rlp.val_at(i + 5)?), rlp.list_at(i + 6)?)
+        } else {
+            (None, Vec::new())
+        };
+        // after access list, same place as blob fee
+        let authorization_list = if tx_type == SET_CODE_TX_TYPE {
+            rlp.list_at(i + 5)?
+        } else {
+            Vec::new()
+        };
+        Ok(EthTx {
+            hash,
+            tx_type,
+            chain_id: Some(rlp.val_at(0)?),
+            nonce: rlp.val_at(1)?,
+            gas_price,
+            max_priority_fee_per_gas,
+            max_fee_per_gas,
+            gas: rlp.val_at(i)?,
+            to: decode_to(&rlp.at(i + 1)?)?,
+            value: rlp.val_at(i + 2)?,
+            input: rlp.val_at(i + 3)?,
+            access_list: decode_access_list(&rlp.at(i + 4)?)?,
+            max_fee_per_blob_gas,
+            blob_versioned_hashes,
+            authorization_list,
+        })
+    }
+
+    // Nonce of contract creation tx, None for call. Decodes no hash or payload
+    pub fn create_nonce(data: &[u8]) -> Result<Option<u64>> {
+        let Some(first) = data.first() else {
+            bail!("Empty transaction");
+        };
+        // legacy: nonce, gas price, gas, to; typed: chain id, nonce, fee fields, gas, to
+        let (rlp, nonce_at, to_at) = match *first {
+            first if first >= 0xc0 => (Rlp::new(data), 0, 3),
+            ACCESS_LIST_TX_TYPE => (Rlp::new(&data[1..]), 1, 4),
+            DYNAMIC_FEE_TX_TYPE | BLOB_TX_TYPE | SET_CODE_TX_TYPE => (Rlp::new(&data[1..]), 1, 5),
+            tx_type => bail!("Unknown transaction type {}", tx_type),
+        };
+        if decode_to(&rlp.at(to_at)?)?.is_some() {
+            return Ok(None);
+        }
+        Ok(Some(rlp.val_at(nonce_at)?))
+    }
+}
+
+// Trailing field of later fork
+fn optional_at<T: Decodable>(rlp: &Rlp, i: usize) -> Result<Option<T>> {
+    if i < rlp.item_count()? {
+        Ok(Some(rlp.val_at(i)?))
+    } else {
+        Ok(None)
+    }
+}
+
+fn decode_to(rlp: &Rlp) -> Result<Option<Address>> {
+    if rlp.is_empty() {
+        Ok(None)
+    } else {
+        Ok(Some(Address::decode(rlp)?))
+    }
+}
+
+fn decode_access_list(rlp: &Rlp) -> Result<Vec<AccessListItem>> {
+    rlp.iter()
+        .map(|item| {
+            Ok(AccessListItem {
+                address: item.val_at(0)?,
+                storage_keys: item.list_at(1)?,
+            })
+        })
+        .collect()
+}
+
+// Key of block tables: number u64 BE, hash
+pub fn block_key(number: u64, hash: &H256) -> Vec<u8> {
+    let mut key = number.to_be_bytes().to_vec();
+    key.extend_from_slice(hash.as_bytes());
+    key
+}
+
+pub fn read_header(txn: &Transaction<RO, NoWriteMap>, number: u64, hash: &H256) -> Result<Option<Header>> {
+    let db = txn.open_db(Some(HEADERS_TABLE))?;
+    let header: Option<Vec<u8>> = txn.get(&db, &block_key(number, hash))?;
+    header.map(|header| Header::decode(&header)).transpose()
+}
+
+pub fn read_body(txn: &Transaction<RO, NoWriteMap>, number: u64, hash: &H256) -> Result<Option<BodyForStorage>> {
+    let db = txn.open_db(Some(BLOCK_BODY_TABLE))?;
+    let body: Option<Vec<u8>> = txn.get(&db, &block_key(number, hash))?;
+    body.map(|body| BodyForStorage::decode(&body)).transpose()
+}
+
+// Senders of block txs, recovered by Erigon Senders stage
+pub fn read_senders(txn: &Transaction<RO, NoWriteMap>, number: u64, hash: &H256) -> Result<Vec<Address>> {
+    let db = txn.open_db(Some(SENDERS_TABLE))?;
+    let senders: Option<Vec<u8>> = txn.get(&db, &block_key(number, hash))?;
+    Ok(senders
+        .unwrap_or_default()
+        .chunks_exact(Address::len_bytes())
+        .map(Address::from_slice)
+        .collect())
+}
+
+// Cursor over BlockTransaction, reused for bodies of range
+pub fn tx_cursor<'txn>(txn: &'txn Transaction<RO, NoWriteMap>) -> Result<Cursor<'txn, RO>> {
+    let db = txn.open_db(Some(ETH_TX_TABLE))?;
+    Ok(txn.cursor(&db)?)
+}
+
+// Stored txs of block body
+pub fn read_transactions(cursor: &mut Cursor<'_, RO>, body: &BodyForStorage) -> Result<Vec<Vec<u8>>> {
+    let (first_id, count) = body.tx_range();
+    if count == 0 {
+        return Ok(Vec::new());
+    }
+    let mut txs = Vec::with_capacity(count as usize);
+    let ids = first_id..first_id + count as u64;
+    for (id, item) in ids.zip(cursor.iter_from::<Vec<u8>, Vec<u8>>(&first_id.to_be_bytes())) {
+        let (key, tx) = item?;
+        // gap in table, next entry belongs to other body
+        if key != id.to_be_bytes() {
+            bail!("Missing transaction {} of {}-{}", id, first_id, first_id + count as u64 - 1);
+        }
+        txs.push(tx);
+    }
+    if txs.len() != count as usize {
+        bail!("Missing transactions {}-{}", first_id, first_id + count as u64 - 1);
+    }
+    Ok(txs)
+}
+
+#[cfg(test)]
+pub(crate) mod test {
+    use crate::db::block::{
+        read_transactions, tx_cursor, Authorization, BodyForStorage, EthTx, Header, Withdrawal, BLOB_TX_TYPE,
+        DYNAMIC_FEE_TX_TYPE, SET_CODE_TX_TYPE,
+    };
+    use crate::db::ETH_TX_TABLE;
+    use ethers::types::{Address, H256, U256};
+    use ethers::utils::keccak256;
+    use ethers::utils::rlp::{Encodable, RlpStream};
+    use libmdbx::{DatabaseFlags, Environment, NoWriteMap, WriteFlags};
+
+    #[test]
+    fn decode_transactions() {
+        let mut body = RlpStream::new_list(3);
+        body.append(&100_u64).append(&5_u32).begin_list(0);
+        let body = BodyForStorage::decode(&body.out()).unwrap();
+        assert_eq!(body.tx_range(), (101, 3));
+        assert!(body.withdrawals.is_empty());
+
+        let mut body = RlpStream::new_list(4);
+        body.append(&100_u64).append(&2_u32).begin_list(0);
+        body.begin_list(1)
+            .begin_list(4)
+            .append(&9_u64)
+            .append(&1_000_u64)
+            .append(&Address::from_low_u64_be(3))
+            .append(&32_000_000_000_u64);
+        let body = BodyForStorage::decode(&body.out()).unwrap();
+        assert_eq!(body.tx_range(), (101, 0));
+        assert_eq!(
+            body.withdrawals,
+            vec![Withdrawal {
+                index: 9,
+                validator_index: 1_000,
+                address: Address::from_low_u64_be(3),
+                amount_gwei: 32_000_000_000,
+            }]
+        );
+
+        let to = Address::from_low_u64_be(2);
+        let mut legacy = RlpStream::new_list(9);
+        legacy
+            .append(&7_u64)
+            .append(&U256::from(20_000_000_000_u64))
+            .append(&21_000_u64)
+            .append(&"")
+            .append(&U256::from(1_000))
+            .append(&vec![1_u8, 2])
+            .append(&37_u64)
+            .append(&U256::one())
+            .append(&U256::one());
+        let raw = legacy.out().to_vec();
+        let tx = EthTx::decode(&raw).unwrap();
+        assert_eq!(tx.hash, H256::from(keccak256(&raw)));
+        assert_eq!((tx.tx_type, tx.chain_id, tx.nonce, tx.gas), (0, Some(1), 7, 21_000));
+        assert_eq!(tx.gas_price, Some(U256::from(20_000_000_000_u64)));
+        // contract creation
+        assert_eq!(tx.to, None);
+        assert_eq!(tx.input, vec![1, 2]);
+        assert_eq!(EthTx::create_nonce(&raw).unwrap(), Some(7));
+        // pre-EIP-155 signature
+        let mut legacy = RlpStream::new_list(9);
+        legacy.append(&7_u64).append(&U256::one()).append(&21_000_u64).append(&to).append(&U256::zero());
+        legacy.append(&"").append(&27_u64).append(&U256::one()).append(&U256::one());
+        assert_eq!(EthTx::decode(&legacy.out()).unwrap().chain_id, None);
+
+        let mut payload = RlpStream::new_list(12);
+        payload
+            .append(&1_u64)
+            .append(&4_u64)
+            .append(&U256::from(1))
+            .append(&U256::from(20))
+            .append(&60_000_u64)
+            .append(&"")
+            .append(&U256::zero())
+            .append(&vec![0x60_u8, 0x80])
+            .begin_list(0);
+        payload.append(&0_u64).append(&U256::one()).append(&U256::one());
+        let mut dynamic_fee = vec![DYNAMIC_FEE_TX_TYPE];
+        dynamic_fee.extend_from_slice(&payload.out());
+        let tx = EthTx::decode(&dynamic_fee).unwrap();
+        assert_eq!((tx.tx_type, tx.chain_id, tx.nonce), (DYNAMIC_FEE_TX_TYPE, Some(1), 4));
+        assert_eq!((tx.max_priority_fee_per_gas, tx.max_fee_per_gas), (Some(U256::from(1)), Some(U256::from(20))));
+        assert_eq!((tx.gas_price, tx.to, tx.max_fee_per_blob_gas), (None, None, None));
+        assert!(tx.access_list.is_empty() && tx.blob_versioned_hashes.is_empty());
+        assert_eq!(EthTx::create_nonce(&dynamic_fee).unwrap(), Some(4));
+
+        let storage_key = H256::from_low_u64_be(9);
+        let blob_hash = H256::from_low_u64_be(10);
+        let mut payload = RlpStream::new_list(14);
+        payload
+            .append(&1_u64)
+            .append(&8_u64)
+            .append(&U256::from(2))
+            .append(&U256::from(30))
+            .append(&50_000_u64)
+            .append(&to)
+            .append(&U256::zero())
+            .append(&Vec::<u8>::new());
+        payload.begin_list(1).begin_list(2).append(&to).append_list(&[storage_key]);
+        payload
+            .append(&U256::from(3))
+            .append_list(&[blob_hash])
+            .append(&1_u64)
+            .append(&U256::one())
+            .append(&U256::one());
+        let mut raw = vec![BLOB_TX_TYPE];
+        raw.extend_from_slice(&payload.out());
+        let tx = EthTx::decode(&raw).unwrap();
+        assert_eq!((tx.tx_type, tx.chain_id, tx.nonce, tx.gas), (BLOB_TX_TYPE, Some(1), 8, 50_000));
+        assert_eq!((tx.max_priority_fee_per_gas, tx.max_fee_per_gas), (Some(U256::from(2)), Some(U256::from(30))));
+        assert_eq!(tx.gas_price, None);
+        assert_eq!(tx.to, Some(to));
+        assert_eq!(tx.access_list[0].storage_keys, vec![storage_key]);
+        assert_eq!(tx.max_fee_per_blob_gas, Some(U256::from(3)));
+        assert_eq!(tx.blob_versioned_hashes, vec![blob_hash]);
+        assert_eq!(EthTx::create_nonce(&raw).unwrap(), None);
+
+        let authorization = Authorization {
+            chain_id: U256::zero(),
+            address: to,
+            nonce: 4,
+            y_parity: 1,
+            r: U256::one(),
+            s: U256::one(),
+        };
+        let mut payload = RlpStream::new_list(13);
+        payload
+            .append(&1_u64)
+            .append(&9_u64)
+            .append(&U256::from(2))
+            .append(&U256::from(30))
+            .append(&80_000_u64)
+            .append(&to)
+            .append(&U256::zero())
+            .append(&Vec::<u8>::new())
+            .begin_list(0);
+        payload.begin_list(1).begin_list(6);
+        payload
+            .append(&authorization.chain_id)
+            .append(&authorization.address)
+            .append(&authorization.nonce)
+            .append(&authorization.y_parity)
+            .append(&authorization.r)
+            .append(&authorization.s);
+        payload.append(&0_u64).append(&U256::one()).append(&U256::one());
+        let mut set_code = vec![SET_CODE_TX_TYPE];
+        set_code.extend_from_slice(&payload.out());
+        let tx = EthTx::decode(&set_code).unwrap();
+        assert_eq!((tx.tx_type, tx.nonce, tx.gas, tx.to), (SET_CODE_TX_TYPE, 9, 80_000, Some(to)));
+        assert_eq!(tx.max_fee_per_gas, Some(U256::from(30)));
+        assert_eq!(tx.authorization_list, vec![authorization]);
+        assert_eq!(tx.max_fee_per_blob_gas, None);
+
+        // dynamic fee tx truncated
+        raw[0] = DYNAMIC_FEE_TX_TYPE;
+        assert!(EthTx::decode(&raw[..20]).is_err());
+        assert!(EthTx::decode(&[5, 0xc0]).is_err());
+        assert!(EthTx::create_nonce(&[5, 0xc0]).is_err());
+    }
+
+    // RLP header with fields of forks after London
+    pub fn header_rlp(number: u64, forks: &[&dyn Encodable]) -> Vec<u8> {
+        let mut header = RlpStream::new_list(15 + forks.len());
+        header
+            .append(&H256::from_low_u64_be(number.saturating_sub(1)))
+            .append(&H256::zero())
+            .append(&Address::from_low_u64_be(7))
+            .append(&H256::from_low_u64_be(8))
+            .append(&H256::zero())
+            .append(&H256::zero())
+            .append(&vec![0_u8; 256])
+            .append(&U256::from(131_072))
+            .append(&number)
+            .append(&30_000_000_u64)
+            .append(&21_000_u64)
+            .append(&(1_700_000_000 + number * 12))
+            .append(&b"extra".to_vec())
+            .append(&H256::zero())
+            .append(&[0_u8; 8].to_vec());
+        for field in forks {
+            header.append_raw(&field.rlp_bytes(), 1);
+        }
+        header.out().to_vec()
+    }
+
+    #[test]
+    fn decode_header() {
+        let header = Header::decode(&header_rlp(1, &[])).unwrap();
+        assert_eq!((header.number, header.gas_limit, header.gas_used), (1, 30_000_000, 21_000));
+        assert_eq!(header.miner, Address::from_low_u64_be(7));
+        assert_eq!(header.difficulty, U256::from(131_072));
+        assert_eq!(header.extra_data, b"extra".to_vec());
+        assert_eq!(header.base_fee_per_gas, None);
+
+        let root = H256::from_low_u64_be(5);
+        let cancun: [&dyn Encodable; 5] = [&U256::from(7), &root, &131_072_u64, &0_u64, &root];
+        let header = Header::decode(&header_rlp(2, &cancun)).unwrap();
+        assert_eq!(header.base_fee_per_gas, Some(U256::from(7)));
+        assert_eq!(header.withdrawals_root, Some(root));
+        assert_eq!((header.blob_gas_used, header.excess_blob_gas), (Some(131_072), Some(0)));
+        assert_eq!(header.parent_beacon_block_root, Some(root));
+    }
+
+    #[test]
+    fn read_transactions_gap() {
+        let dir = tempfile::tempdir().unwrap();
+        let env: Environment<NoWriteMap> = Environment::new().set_max_dbs(8).open(dir.path()).unwrap();
+        {
+            let txn = env.begin_rw_txn().unwrap();
+            let db = txn.create_db(Some(ETH_TX_TABLE), DatabaseFlags::default()).unwrap();
+            // tx 3 missing, 4 belongs to next body
+            for id in [1u64, 2, 4] {
+                txn.put(&db, id.to_be_bytes(), [id as u8], WriteFlags::UPSERT).unwrap();
+            }
+            txn.commit().unwrap();
+        }
+        let txn = env.begin_ro_txn().unwrap();
+        let mut cursor = tx_cursor(&txn).unwrap();
+        let body = |tx_amount| BodyForStorage {
+            base_tx_id: 0,
+            tx_amount,
+            withdrawals: Vec::new(),
+        };
+        assert_eq!(read_transactions(&mut cursor, &body(4)).unwrap(), vec![vec![1], vec![2]]);
+        let err = read_transactions(&mut cursor, &body(5)).unwrap_err();
+        assert_eq!(err.to_string(), "Missing transaction 3 of 1-3");
+    }
+}
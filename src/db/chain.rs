@@ -0,0 +1,323 @@
+use crate::db::{
+    CANONICAL_HEADER_TABLE, CONFIG_TABLE, DB_DATA_FILE, HEADER_NUMBER_TABLE, HEAD_HEADER_TABLE,
+    LAST_FORKCHOICE_TABLE, SYNC_STAGE_TABLE,
+};
+use anyhow::{anyhow, bail, Result};
+use byteorder::{BigEndian, ByteOrder};
+use ethers::types::H256;
+use libmdbx::{Database, Environment, NoWriteMap, Transaction, RO};
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+
+// Erigon stage names, key of SyncStage table
+pub const STAGE_HEADERS: &str = "Headers";
+pub const STAGE_BODIES: &str = "Bodies";
+pub const STAGE_EXECUTION: &str = "Execution";
+pub const STAGE_LOG_INDEX: &str = "LogIndex";
+pub const STAGE_ACCOUNT_HISTORY_INDEX: &str = "AccountHistoryIndex";
+pub const STAGE_STORAGE_HISTORY_INDEX: &str = "StorageHistoryIndex";
+// LastForkchoice keys
+pub const SAFE_BLOCK_KEY: &str = "safeBlockHash";
+pub const FINALIZED_BLOCK_KEY: &str = "finalizedBlockHash";
+
+#[derive(Debug, Clone, Serialize)]
+pub struct BlockRef {
+    pub number: u64,
+    pub hash: H256,
+}
+
+// How far Erigon has synced
+#[derive(Debug, Clone, Serialize)]
+pub struct ChainStatus {
+    pub chain_id: Option<u64>,
+    // header set by HeadHeaderKey
+    pub head_header: Option<BlockRef>,
+    // highest CanonicalHeader entry
+    pub canonical_head: Option<BlockRef>,
+    // fork choice of consensus layer, none before merge
+    pub safe: Option<BlockRef>,
+    pub finalized: Option<BlockRef>,
+    pub headers: Option<u64>,
+    pub bodies: Option<u64>,
+    pub execution: Option<u64>,
+    pub log_index: Option<u64>,
+    // progress of all stages
+    pub stages: BTreeMap<String, u64>,
+    pub db_size_bytes: u64,
+}
+
+pub fn read_chain_status(env: &Environment<NoWriteMap>, db_path: &Path) -> Result<ChainStatus> {
+    let txn = env.begin_ro_txn()?;
+    let stages = read_stages(&txn)?;
+    let canonical_head = match open_table(&txn, CANONICAL_HEADER_TABLE)? {
+        Some(db) => match txn.cursor(&db)?.last::<Vec<u8>, Vec<u8>>()? {
+            Some((key, hash)) => {
+                let (number, hash) = decode_canonical(&key, &hash)?;
+                Some(BlockRef { number, hash })
+            }
+            None => None,
+        },
+        None => None,
+    };
+    let head_header = read_head_header(&txn)?;
+    let safe = read_forkchoice(&txn, SAFE_BLOCK_KEY)?;
+    let finalized = read_forkchoice(&txn, FINALIZED_BLOCK_KEY)?;
+    let chain_id = read_chain_id(&txn)?;
+    let db_size_bytes = fs::metadata(db_path.join(DB_DATA_FILE))?.len();
+
+    Ok(ChainStatus {
+        chain_id,
+        head_header,
+        canonical_head,
+        safe,
+        finalized,
+        headers: stages.get(STAGE_HEADERS).copied(),
+        bodies: stages.get(STAGE_BODIES).copied(),
+        execution: stages.get(STAGE_EXECUTION).copied(),
+        log_index: stages.get(STAGE_LOG_INDEX).copied(),
+        stages,
+        db_size_bytes,
+    })
+}
+
+// Block reached by stage, 0 when stage never ran
+pub fn read_stage_progress(txn: &Transaction<RO, NoWriteMap>, stage: &str) -> Result<u64> {
+    Ok(read_stages(txn)?.get(stage).copied().unwrap_or_default())
+}
+
+// Executed block and fork choice blocks at same snapshot
+#[derive(Debug, Clone, Copy)]
+pub struct SyncHead {
+    pub executed: u64,
+    pub safe: Option<u64>,
+    pub finalized: Option<u64>,
+}
+
+pub fn read_sync_head(txn: &Transaction<RO, NoWriteMap>) -> Result<SyncHead> {
+    Ok(SyncHead {
+        executed: read_stage_progress(txn, STAGE_EXECUTION)?,
+        safe: read_forkchoice(txn, SAFE_BLOCK_KEY)?.map(|b| b.number),
+        finalized: read_forkchoice(txn, FINALIZED_BLOCK_KEY)?.map(|b| b.number),
+    })
+}
+
+// Canonical hashes of blocks from..=to
+pub fn canonical_hashes(
+    txn: &Transaction<RO, NoWriteMap>,
+    from_block: u64,
+    to_block: u64,
+) -> Result<Vec<(u64, H256)>> {
+    let db = txn.open_db(Some(CANONICAL_HEADER_TABLE))?;
+    let mut cursor = txn.cursor(&db)?;
+    let mut hashes = Vec::new();
+    for item in cursor.iter_from::<Vec<u8>, Vec<u8>>(&from_block.to_be_bytes()) {
+        let (key, hash) = item?;
+        let (number, hash) = decode_canonical(&key, &hash)?;
+        if number > to_block {
+            break;
+        }
+        hashes.push((number, hash));
+    }
+    Ok(hashes)
+}
+
+// CanonicalHeader: block number u64 BE -> block hash
+pub fn decode_canonical(key: &[u8], hash: &[u8]) -> Result<(u64, H256)> {
+    if key.len() != 8 || hash.len() != 32 {
+        bail!("Invalid canonical header entry");
+    }
+    Ok((BigEndian::read_u64(key), H256::from_slice(hash)))
+}
+
+// Last block of from..=to executed with canonical hashes of all blocks since from_block,
+// None if from_block is not there yet
+pub fn canonical_head(txn: &Transaction<RO, NoWriteMap>, from_block: u64, to_block: u64) -> Result<Option<u64>> {
+    let to_block = to_block.min(read_stage_progress(txn, STAGE_EXECUTION)?);
+    let db = txn.open_db(Some(CANONICAL_HEADER_TABLE))?;
+    let mut cursor = txn.cursor(&db)?;
+    let mut head = None;
+    let blocks = cursor.iter_from::<Vec<u8>, Vec<u8>>(&from_block.to_be_bytes());
+    for (block_n, item) in (from_block..=to_block).zip(blocks) {
+        let (key, _) = item?;
+        // gap: block unwound and not executed again yet
+        if key != block_n.to_be_bytes() {
+            break;
+        }
+        head = Some(block_n);
+    }
+    Ok(head)
+}
+
+// Lowest exported block which is not canonical anymore: hash changed or block unwound
+pub fn find_fork(txn: &Transaction<RO, NoWriteMap>, exported: &[(u64, H256)]) -> Result<Option<u64>> {
+    let db = txn.open_db(Some(CANONICAL_HEADER_TABLE))?;
+    for (number, hash) in exported {
+        let canonical: Option<Vec<u8>> = txn.get(&db, &number.to_be_bytes())?;
+        if canonical.as_deref() != Some(hash.as_bytes()) {
+            return Ok(Some(*number));
+        }
+    }
+    Ok(None)
+}
+
+fn read_stages(txn: &Transaction<RO, NoWriteMap>) -> Result<BTreeMap<String, u64>> {
+    let db = txn.open_db(Some(SYNC_STAGE_TABLE))?;
+    let mut cursor = txn.cursor(&db)?;
+    let mut stages = BTreeMap::new();
+    for item in cursor.iter_start::<Vec<u8>, Vec<u8>>() {
+        let (key, value) = item?;
+        if value.len() != 8 {
+            continue;
+        }
+        stages.insert(String::from_utf8_lossy(&key).to_string(), BigEndian::read_u64(&value));
+    }
+    Ok(stages)
+}
+
+fn read_head_header(txn: &Transaction<RO, NoWriteMap>) -> Result<Option<BlockRef>> {
+    let Some(db) = open_table(txn, HEAD_HEADER_TABLE)? else {
+        return Ok(None);
+    };
+    let hash: Option<Vec<u8>> = txn.get(&db, HEAD_HEADER_TABLE.as_bytes())?;
+    block_ref(txn, hash)
+}
+
+// Table missing before merge or in older Erigon
+fn read_forkchoice(txn: &Transaction<RO, NoWriteMap>, key: &str) -> Result<Option<BlockRef>> {
+    let Some(db) = open_table(txn, LAST_FORKCHOICE_TABLE)? else {
+        return Ok(None);
+    };
+    let hash: Option<Vec<u8>> = txn.get(&db, key.as_bytes())?;
+    block_ref(txn, hash)
+}
+
+// Number of header hash, unknown or zero hash is none
+fn block_ref(txn: &Transaction<RO, NoWriteMap>, hash: Option<Vec<u8>>) -> Result<Option<BlockRef>> {
+    let Some(hash) = hash.filter(|h| h.len() == 32 && *h != H256::zero().as_bytes()) else {
+        return Ok(None);
+    };
+    let Some(db) = open_table(txn, HEADER_NUMBER_TABLE)? else {
+        return Ok(None);
+    };
+    let number: Option<Vec<u8>> = txn.get(&db, &hash)?;
+    Ok(number.map(|n| BlockRef {
+        number: BigEndian::read_u64(&n),
+        hash: H256::from_slice(&hash),
+    }))
+}
+
+// Config table: genesis hash -> chain config JSON
+fn read_chain_id(txn: &Transaction<RO, NoWriteMap>) -> Result<Option<u64>> {
+    let Some(db) = open_table(txn, CANONICAL_HEADER_TABLE)? else {
+        return Ok(None);
+    };
+    let genesis: Option<Vec<u8>> = txn.get(&db, &0u64.to_be_bytes())?;
+    let Some(genesis) = genesis else {
+        return Ok(None);
+    };
+    let Some(db) = open_table(txn, CONFIG_TABLE)? else {
+        return Ok(None);
+    };
+    let config: Option<Vec<u8>> = txn.get(&db, &genesis)?;
+    let Some(config) = config else {
+        return Ok(None);
+    };
+    let config: serde_json::Value =
+        serde_json::from_slice(&config).map_err(|err| anyhow!("Invalid chain config {}", err))?;
+    Ok(config["chainId"].as_u64())
+}
+
+// Table not created yet on partially synced db is none
+fn open_table<'txn>(txn: &'txn Transaction<RO, NoWriteMap>, table: &str) -> Result<Option<Database<'txn>>> {
+    match txn.open_db(Some(table)) {
+        Ok(db) => Ok(Some(db)),
+        Err(libmdbx::Error::NotFound) => Ok(None),
+        Err(err) => Err(err.into()),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::db::chain::{
+        read_chain_status, read_stage_progress, read_sync_head, FINALIZED_BLOCK_KEY, SAFE_BLOCK_KEY, STAGE_EXECUTION,
+        STAGE_LOG_INDEX,
+    };
+    use crate::db::{
+        open_env, CANONICAL_HEADER_TABLE, CONFIG_TABLE, HEADER_NUMBER_TABLE, HEAD_HEADER_TABLE, LAST_FORKCHOICE_TABLE,
+        SYNC_STAGE_TABLE,
+    };
+    use ethers::types::H256;
+    use libmdbx::{DatabaseFlags, Environment, NoWriteMap, WriteFlags};
+
+    #[test]
+    fn chain_status() {
+        let dir = tempfile::tempdir().unwrap();
+        let db_path = dir.path();
+        let genesis = H256::from_low_u64_be(1);
+        let head = H256::from_low_u64_be(2);
+        let finalized = H256::from_low_u64_be(3);
+        {
+            let env: Environment<NoWriteMap> = Environment::new().set_max_dbs(8).open(db_path).unwrap();
+            let txn = env.begin_rw_txn().unwrap();
+            let put = |table: &str, key: &[u8], value: &[u8]| {
+                let db = txn.create_db(Some(table), DatabaseFlags::default()).unwrap();
+                txn.put(&db, key, value, WriteFlags::UPSERT).unwrap();
+            };
+            put(SYNC_STAGE_TABLE, b"Headers", &120u64.to_be_bytes());
+            put(SYNC_STAGE_TABLE, b"Execution", &100u64.to_be_bytes());
+            put(CANONICAL_HEADER_TABLE, &0u64.to_be_bytes(), genesis.as_bytes());
+            put(CANONICAL_HEADER_TABLE, &120u64.to_be_bytes(), head.as_bytes());
+            put(HEAD_HEADER_TABLE, HEAD_HEADER_TABLE.as_bytes(), head.as_bytes());
+            put(HEADER_NUMBER_TABLE, head.as_bytes(), &120u64.to_be_bytes());
+            put(CONFIG_TABLE, genesis.as_bytes(), br#"{"chainId":11155111}"#);
+            put(HEADER_NUMBER_TABLE, finalized.as_bytes(), &64u64.to_be_bytes());
+            put(LAST_FORKCHOICE_TABLE, FINALIZED_BLOCK_KEY.as_bytes(), finalized.as_bytes());
+            // not set yet
+            put(LAST_FORKCHOICE_TABLE, SAFE_BLOCK_KEY.as_bytes(), H256::zero().as_bytes());
+            txn.commit().unwrap();
+        }
+
+        let env = open_env(db_path).unwrap();
+        let status = read_chain_status(&env, db_path).unwrap();
+        assert_eq!(status.chain_id, Some(11155111));
+        assert_eq!(status.headers, Some(120));
+        assert_eq!(status.execution, Some(100));
+        assert_eq!(status.log_index, None);
+        assert_eq!(status.stages.len(), 2);
+        let canonical_head = status.canonical_head.unwrap();
+        assert_eq!((canonical_head.number, canonical_head.hash), (120, head));
+        assert_eq!(status.head_header.unwrap().number, 120);
+        assert_eq!(status.finalized.unwrap().number, 64);
+        assert!(status.safe.is_none());
+        assert!(status.db_size_bytes > 0);
+        let txn = env.begin_ro_txn().unwrap();
+        let head = read_sync_head(&txn).unwrap();
+        assert_eq!((head.executed, head.safe, head.finalized), (100, None, Some(64)));
+        assert_eq!(read_stage_progress(&txn, STAGE_EXECUTION).unwrap(), 100);
+        assert_eq!(read_stage_progress(&txn, STAGE_LOG_INDEX).unwrap(), 0);
+        drop(txn);
+    }
+
+    #[test]
+    fn chain_status_partial_db() {
+        let dir = tempfile::tempdir().unwrap();
+        {
+            let env: Environment<NoWriteMap> = Environment::new().set_max_dbs(8).open(dir.path()).unwrap();
+            let txn = env.begin_rw_txn().unwrap();
+            let db = txn.create_db(Some(SYNC_STAGE_TABLE), DatabaseFlags::default()).unwrap();
+            txn.put(&db, b"Headers", 50u64.to_be_bytes(), WriteFlags::UPSERT).unwrap();
+            // head hash without HeaderNumber and Config tables
+            let db = txn.create_db(Some(HEAD_HEADER_TABLE), DatabaseFlags::default()).unwrap();
+            txn.put(&db, HEAD_HEADER_TABLE, H256::from_low_u64_be(2), WriteFlags::UPSERT)
+                .unwrap();
+            txn.commit().unwrap();
+        }
+
+        let status = read_chain_status(&open_env(dir.path()).unwrap(), dir.path()).unwrap();
+        assert_eq!(status.headers, Some(50));
+        assert_eq!(status.stages.len(), 1);
+        assert!(status.chain_id.is_none() && status.canonical_head.is_none() && status.head_header.is_none());
+    }
+}
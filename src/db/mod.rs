@@ -0,0 +1,115 @@
+pub mod block;
+pub mod chain;
+pub mod state;
+
+use anyhow::{anyhow, Result};
+use libmdbx::{Environment, EnvironmentFlags, Error, Mode, NoWriteMap};
+use std::path::Path;
+
+// Erigon tables, see erigon-lib kv/tables.go
+pub const LOGS_TABLE: &str = "TransactionLog";
+// stage name -> block number u64 BE
+pub const SYNC_STAGE_TABLE: &str = "SyncStage";
+// block number u64 BE -> CBOR receipts of canonical block
+pub const RECEIPTS_TABLE: &str = "Receipt";
+// block number u64 BE, hash -> RLP header
+pub const HEADERS_TABLE: &str = "Header";
+// block number u64 BE, hash -> RLP BodyForStorage
+pub const BLOCK_BODY_TABLE: &str = "BlockBody";
+// EthTx: tx id u64 BE -> tx binary encoding
+pub const ETH_TX_TABLE: &str = "BlockTransaction";
+// block number u64 BE, hash -> senders of block txs, 20 bytes each
+pub const SENDERS_TABLE: &str = "TxSender";
+// block number u64 BE -> dup sorted address, account before block
+pub const ACCOUNT_CHANGESET_TABLE: &str = "AccountChangeSet";
+// block number u64 BE, address, incarnation u64 BE -> dup sorted slot, value before block
+pub const STORAGE_CHANGESET_TABLE: &str = "StorageChangeSet";
+// address -> account, address, incarnation u64 BE -> dup sorted slot, value
+pub const PLAIN_STATE_TABLE: &str = "PlainState";
+// address, shard last block u64 BE -> roaring64 bitmap of blocks changing account
+pub const ACCOUNT_HISTORY_TABLE: &str = "AccountHistory";
+// address, slot, shard last block u64 BE -> roaring64 bitmap of blocks changing slot of any incarnation
+pub const STORAGE_HISTORY_TABLE: &str = "StorageHistory";
+// block number u64 BE -> block hash
+pub const CANONICAL_HEADER_TABLE: &str = "CanonicalHeader";
+// HeadHeaderKey, single key same as table name -> block hash
+pub const HEAD_HEADER_TABLE: &str = "LastHeader";
+// block hash -> block number u64 BE
+pub const HEADER_NUMBER_TABLE: &str = "HeaderNumber";
+// fork choice key -> block hash, set by engine API forkchoiceUpdated
+pub const LAST_FORKCHOICE_TABLE: &str = "LastForkchoice";
+// genesis hash -> chain config JSON
+pub const CONFIG_TABLE: &str = "Config";
+pub const DB_DATA_FILE: &str = "mdbx.dat";
+// Erigon chaindata tables the reader depends on
+pub const REQUIRED_TABLES: [&str; 3] = [LOGS_TABLE, SYNC_STAGE_TABLE, CANONICAL_HEADER_TABLE];
+const MAX_DBS: usize = 32;
+
+// Read-only env next to running Erigon
+pub fn open_env(db_path: &Path) -> Result<Environment<NoWriteMap>> {
+    let flags = EnvironmentFlags {
+        mode: Mode::ReadOnly,
+        exclusive: false,
+        accede: true,
+        no_rdahead: true,
+        no_meminit: true,
+        ..Default::default()
+    };
+    Environment::new()
+        .set_flags(flags)
+        .set_max_dbs(MAX_DBS)
+        .open(db_path)
+        .map_err(|err| match err {
+            // LCK file written by other libmdbx version, see storage module notes
+            Error::VersionMismatch | Error::Incompatible => {
+                anyhow!("Incompatible LCK file, libmdbx version differs from Erigon: {}", err)
+            }
+            err => anyhow!("Invalid db {}", err),
+        })
+}
+
+// Required tables exist
+pub fn check_db(env: &Environment<NoWriteMap>) -> Result<()> {
+    let txn = env.begin_ro_txn()?;
+    for table in REQUIRED_TABLES {
+        txn.open_db(Some(table)).map_err(|err| match err {
+            Error::NotFound => anyhow!("Missing table {}", table),
+            err => anyhow!("Open table {} err:{}", table, err),
+        })?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use crate::db::{check_db, open_env, REQUIRED_TABLES};
+    use libmdbx::{DatabaseFlags, Environment, NoWriteMap};
+    use std::fs;
+
+    #[test]
+    fn check_tables() {
+        let dir = tempfile::tempdir().unwrap();
+        let db_path = dir.path().join("chaindata");
+        assert!(open_env(&db_path).is_err());
+
+        fs::create_dir_all(&db_path).unwrap();
+        {
+            let env: Environment<NoWriteMap> = Environment::new().set_max_dbs(8).open(&db_path).unwrap();
+            let txn = env.begin_rw_txn().unwrap();
+            txn.create_db(Some(REQUIRED_TABLES[0]), DatabaseFlags::default()).unwrap();
+            txn.commit().unwrap();
+        }
+        let err = check_db(&open_env(&db_path).unwrap()).unwrap_err();
+        assert_eq!(err.to_string(), format!("Missing table {}", REQUIRED_TABLES[1]));
+
+        {
+            let env: Environment<NoWriteMap> = Environment::new().set_max_dbs(8).open(&db_path).unwrap();
+            let txn = env.begin_rw_txn().unwrap();
+            for table in REQUIRED_TABLES {
+                txn.create_db(Some(table), DatabaseFlags::default()).unwrap();
+            }
+            txn.commit().unwrap();
+        }
+        check_db(&open_env(&db_path).unwrap()).unwrap();
+    }
+}
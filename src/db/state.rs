@@ -0,0 +1,289 @@
+use crate::db::{
+    ACCOUNT_CHANGESET_TABLE, ACCOUNT_HISTORY_TABLE, PLAIN_STATE_TABLE, STORAGE_CHANGESET_TABLE, STORAGE_HISTORY_TABLE,
+};
+use anyhow::{bail, Result};
+use byteorder::{BigEndian, ByteOrder};
+use ethers::types::{Address, H256, U256};
+use libmdbx::{NoWriteMap, Transaction, RO};
+use roaring::RoaringTreemap;
+
+// Account or storage slot of contract incarnation
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum StateKey {
+    Account(Address),
+    Storage(Address, u64, H256),
+}
+
+impl StateKey {
+    pub fn address(&self) -> Address {
+        match self {
+            StateKey::Account(address) | StateKey::Storage(address, _, _) => *address,
+        }
+    }
+}
+
+// Value before block changed it: account encoded for storage or trimmed storage value, empty if not set
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StateChange {
+    pub block_n: u64,
+    pub key: StateKey,
+    pub prev: Vec<u8>,
+}
+
+impl StateChange {
+    // AccountChangeSet: block number u64 BE -> dup sorted address, previous account
+    pub fn from_account_change(key: &[u8], value: &[u8]) -> Result<StateChange> {
+        if key.len() != 8 || value.len() < 20 {
+            bail!("Invalid account change set entry");
+        }
+        Ok(StateChange {
+            block_n: BigEndian::read_u64(key),
+            key: StateKey::Account(Address::from_slice(&value[..20])),
+            prev: value[20..].to_vec(),
+        })
+    }
+
+    // StorageChangeSet: block number u64 BE, address, incarnation u64 BE -> dup sorted slot, previous value
+    pub fn from_storage_change(key: &[u8], value: &[u8]) -> Result<StateChange> {
+        if key.len() != 36 || value.len() < 32 {
+            bail!("Invalid storage change set entry");
+        }
+        Ok(StateChange {
+            block_n: BigEndian::read_u64(key),
+            key: StateKey::Storage(
+                Address::from_slice(&key[8..28]),
+                BigEndian::read_u64(&key[28..]),
+                H256::from_slice(&value[..32]),
+            ),
+            prev: value[32..].to_vec(),
+        })
+    }
+}
+
+// Account fields stored in PlainState and AccountChangeSet
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct Account {
+    pub nonce: u64,
+    pub balance: U256,
+    pub incarnation: u64,
+    pub code_hash: Option<H256>,
+}
+
+impl Account {
+    // Field set byte (nonce, balance, incarnation, code hash bits), then length-prefixed big-endian fields.
+    // None when account does not exist
+    pub fn decode_for_storage(data: &[u8]) -> Result<Option<Account>> {
+        let Some(field_set) = data.first() else {
+            return Ok(None);
+        };
+        let mut pos = 1;
+        let mut field = |bit: u8| -> Result<Option<&[u8]>> {
+            if field_set & bit == 0 {
+                return Ok(None);
+            }
+            let Some(len) = data.get(pos).map(|len| *len as usize) else {
+                bail!("Invalid account encoding");
+            };
+            let Some(value) = data.get(pos + 1..pos + 1 + len) else {
+                bail!("Invalid account encoding");
+            };
+            pos += 1 + len;
+            Ok(Some(value))
+        };
+        let nonce = field(1)?;
+        let balance = field(2)?;
+        let incarnation = field(4)?;
+        let code_hash = field(8)?;
+        let too_long = |field: Option<&[u8]>, max: usize| field.is_some_and(|f| f.len() > max);
+        let bad_hash = code_hash.is_some_and(|h| h.len() != 32);
+        if too_long(nonce, 8) || too_long(balance, 32) || too_long(incarnation, 8) || bad_hash {
+            bail!("Invalid account encoding");
+        }
+        Ok(Some(Account {
+            nonce: nonce.map_or(0, read_uint),
+            balance: balance.map_or(U256::zero(), U256::from_big_endian),
+            incarnation: incarnation.map_or(0, read_uint),
+            code_hash: code_hash.map(H256::from_slice),
+        }))
+    }
+}
+
+// Storage value left-padded to 32 bytes, empty value is zero
+pub fn storage_value(trimmed: &[u8]) -> H256 {
+    let mut value = H256::zero();
+    let len = trimmed.len().min(32);
+    value.0[32 - len..].copy_from_slice(&trimmed[trimmed.len() - len..]);
+    value
+}
+
+// 0-8 bytes big-endian
+fn read_uint(buf: &[u8]) -> u64 {
+    buf.iter().fold(0, |n, b| (n << 8) | *b as u64)
+}
+
+// Latest value of key, empty if not set.
+// PlainState: address -> account, address, incarnation -> dup sorted slot, value
+pub fn read_plain_state(txn: &Transaction<RO, NoWriteMap>, key: &StateKey) -> Result<Vec<u8>> {
+    let db = txn.open_db(Some(PLAIN_STATE_TABLE))?;
+    match key {
+        StateKey::Account(address) => Ok(txn.get::<Vec<u8>>(&db, address.as_bytes())?.unwrap_or_default()),
+        StateKey::Storage(address, incarnation, slot) => {
+            let mut plain_key = address.as_bytes().to_vec();
+            plain_key.extend_from_slice(&incarnation.to_be_bytes());
+            let mut cursor = txn.cursor(&db)?;
+            let value: Option<Vec<u8>> = cursor.get_both_range(&plain_key, slot.as_bytes())?;
+            Ok(value
+                .filter(|value| value.starts_with(slot.as_bytes()))
+                .map(|value| value[32..].to_vec())
+                .unwrap_or_default())
+        }
+    }
+}
+
+// First block after `after_block` changing key in history index, any incarnation for storage.
+// Shards are keyed by their last block, last shard by u64::MAX
+pub fn next_change_block(txn: &Transaction<RO, NoWriteMap>, key: &StateKey, after_block: u64) -> Result<Option<u64>> {
+    let (table, prefix) = match key {
+        StateKey::Account(address) => (ACCOUNT_HISTORY_TABLE, address.as_bytes().to_vec()),
+        StateKey::Storage(address, _, slot) => (STORAGE_HISTORY_TABLE, [address.as_bytes(), slot.as_bytes()].concat()),
+    };
+    let Some(from_block) = after_block.checked_add(1) else {
+        return Ok(None);
+    };
+    let db = txn.open_db(Some(table))?;
+    let mut cursor = txn.cursor(&db)?;
+    let mut shard_key = prefix.clone();
+    shard_key.extend_from_slice(&from_block.to_be_bytes());
+    let mut shard: Option<(Vec<u8>, Vec<u8>)> = cursor.set_range(&shard_key)?;
+    while let Some((shard_key, bitmap)) = shard {
+        if shard_key.len() != prefix.len() + 8 || !shard_key.starts_with(&prefix) {
+            break;
+        }
+        let blocks = RoaringTreemap::deserialize_from(bitmap.as_slice())?;
+        if let Some(block_n) = blocks.select(blocks.rank(after_block)) {
+            return Ok(Some(block_n));
+        }
+        shard = cursor.next()?;
+    }
+    Ok(None)
+}
+
+// Value of key before block changed it, None if change set of block has no entry for key
+pub fn read_change(txn: &Transaction<RO, NoWriteMap>, key: &StateKey, block_n: u64) -> Result<Option<Vec<u8>>> {
+    let block_key = block_n.to_be_bytes();
+    let (table, change_key, sub_key) = match key {
+        StateKey::Account(address) => (ACCOUNT_CHANGESET_TABLE, block_key.to_vec(), address.as_bytes()),
+        StateKey::Storage(address, incarnation, slot) => (
+            STORAGE_CHANGESET_TABLE,
+            [&block_key[..], address.as_bytes(), &incarnation.to_be_bytes()].concat(),
+            slot.as_bytes(),
+        ),
+    };
+    let db = txn.open_db(Some(table))?;
+    let mut cursor = txn.cursor(&db)?;
+    let value: Option<Vec<u8>> = cursor.get_both_range(&change_key, sub_key)?;
+    Ok(value
+        .filter(|value| value.starts_with(sub_key))
+        .map(|value| value[sub_key.len()..].to_vec()))
+}
+
+#[cfg(test)]
+mod test {
+    use crate::db::state::{next_change_block, read_change, storage_value, Account, StateChange, StateKey};
+    use crate::db::{ACCOUNT_CHANGESET_TABLE, ACCOUNT_HISTORY_TABLE, STORAGE_CHANGESET_TABLE, STORAGE_HISTORY_TABLE};
+    use ethers::types::{Address, H256, U256};
+    use libmdbx::{DatabaseFlags, Environment, NoWriteMap, WriteFlags};
+    use roaring::RoaringTreemap;
+
+    #[test]
+    fn decode_account() {
+        assert_eq!(Account::decode_for_storage(&[]).unwrap(), None);
+        // nonce 5, balance 0x0100, code hash
+        let mut data = vec![0b1011, 1, 5, 2, 1, 0, 32];
+        data.extend_from_slice(H256::from_low_u64_be(7).as_bytes());
+        let account = Account::decode_for_storage(&data).unwrap().unwrap();
+        assert_eq!(account.nonce, 5);
+        assert_eq!(account.balance, U256::from(256));
+        assert_eq!(account.incarnation, 0);
+        assert_eq!(account.code_hash, Some(H256::from_low_u64_be(7)));
+        // empty account
+        assert_eq!(Account::decode_for_storage(&[0]).unwrap(), Some(Account::default()));
+        assert!(Account::decode_for_storage(&[0b10, 3, 1]).is_err());
+        // code hash not 32 bytes
+        assert!(Account::decode_for_storage(&[0b1000, 2, 1, 2]).is_err());
+
+        assert_eq!(storage_value(&[]), H256::zero());
+        assert_eq!(storage_value(&[1, 2]), H256::from_low_u64_be(0x0102));
+    }
+
+    #[test]
+    fn decode_change_sets() {
+        let address = Address::from_low_u64_be(0xa);
+        let change = StateChange::from_account_change(&7u64.to_be_bytes(), &[address.as_bytes(), &[0]].concat());
+        assert_eq!(
+            change.unwrap(),
+            StateChange {
+                block_n: 7,
+                key: StateKey::Account(address),
+                prev: vec![0],
+            }
+        );
+        let slot = H256::from_low_u64_be(1);
+        let key = [&7u64.to_be_bytes(), address.as_bytes(), &2u64.to_be_bytes()].concat();
+        let change = StateChange::from_storage_change(&key, &[slot.as_bytes(), &[5]].concat()).unwrap();
+        assert_eq!((change.key, change.prev), (StateKey::Storage(address, 2, slot), vec![5]));
+        // key without incarnation
+        assert!(StateChange::from_storage_change(&key[..28], slot.as_bytes()).is_err());
+        assert!(StateChange::from_account_change(&7u64.to_be_bytes(), &[1; 19]).is_err());
+    }
+
+    #[test]
+    fn history_index() {
+        let dir = tempfile::tempdir().unwrap();
+        let env: Environment<NoWriteMap> = Environment::new().set_max_dbs(8).open(dir.path()).unwrap();
+        let (a, b) = (Address::from_low_u64_be(0xa), Address::from_low_u64_be(0xb));
+        let slot = H256::from_low_u64_be(1);
+        {
+            let txn = env.begin_rw_txn().unwrap();
+            let bitmap = |blocks: &[u64]| {
+                let mut data = Vec::new();
+                blocks.iter().copied().collect::<RoaringTreemap>().serialize_into(&mut data).unwrap();
+                data
+            };
+            // shards of a: up to block 5, then last shard
+            let db = txn.create_db(Some(ACCOUNT_HISTORY_TABLE), DatabaseFlags::default()).unwrap();
+            for (address, shard, blocks) in [(a, 5u64, vec![2, 5]), (a, u64::MAX, vec![8]), (b, u64::MAX, vec![3])] {
+                let key = [address.as_bytes(), &shard.to_be_bytes()].concat();
+                txn.put(&db, key, bitmap(&blocks), WriteFlags::UPSERT).unwrap();
+            }
+            let db = txn.create_db(Some(STORAGE_HISTORY_TABLE), DatabaseFlags::default()).unwrap();
+            let key = [a.as_bytes(), slot.as_bytes(), &u64::MAX.to_be_bytes()].concat();
+            txn.put(&db, key, bitmap(&[7, 9]), WriteFlags::UPSERT).unwrap();
+            let db = txn.create_db(Some(ACCOUNT_CHANGESET_TABLE), DatabaseFlags::DUP_SORT).unwrap();
+            txn.put(&db, 8u64.to_be_bytes(), [a.as_bytes(), &[0b1, 1, 2]].concat(), WriteFlags::UPSERT)
+                .unwrap();
+            // slot changed by incarnation 2 in block 7
+            let db = txn.create_db(Some(STORAGE_CHANGESET_TABLE), DatabaseFlags::DUP_SORT).unwrap();
+            for (block_n, incarnation, prev) in [(7u64, 2u64, vec![]), (9, 1, vec![2])] {
+                let key = [&block_n.to_be_bytes(), a.as_bytes(), &incarnation.to_be_bytes()].concat();
+                txn.put(&db, key, [slot.as_bytes(), &prev].concat(), WriteFlags::UPSERT).unwrap();
+            }
+            txn.commit().unwrap();
+        }
+
+        let txn = env.begin_ro_txn().unwrap();
+        let account = StateKey::Account(a);
+        assert_eq!(next_change_block(&txn, &account, 0).unwrap(), Some(2));
+        assert_eq!(next_change_block(&txn, &account, 5).unwrap(), Some(8));
+        // shard of b not taken
+        assert_eq!(next_change_block(&txn, &account, 8).unwrap(), None);
+        assert_eq!(read_change(&txn, &account, 8).unwrap(), Some(vec![0b1, 1, 2]));
+        assert_eq!(read_change(&txn, &StateKey::Account(b), 8).unwrap(), None);
+        // history of slot in any incarnation
+        let storage = StateKey::Storage(a, 1, slot);
+        assert_eq!(next_change_block(&txn, &storage, 5).unwrap(), Some(7));
+        assert_eq!(read_change(&txn, &storage, 7).unwrap(), None);
+        assert_eq!(next_change_block(&txn, &storage, 7).unwrap(), Some(9));
+        assert_eq!(read_change(&txn, &storage, 9).unwrap(), Some(vec![2]));
+    }
+}
@@ -0,0 +1,295 @@
+pub mod registry;
+pub mod transfers;
+
+use crate::decode::registry::registry;
+use crate::job::spec::DecodeSpec;
+use crate::types::KvLog;
+use anyhow::{anyhow, bail, Result};
+use ethers::abi::{Abi, Event, HumanReadableParser, ParamType, RawLog, Token};
+use ethers::types::H256;
+use std::collections::{HashMap, HashSet};
+
+// Columns of every event file, before event params
+const LOG_COLUMNS: [&str; 3] = ["block_n", "tx_n", "contract"];
+
+// Parquet column of event param
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ColumnType {
+    // uint < 64, int <= 64 bits
+    Int64,
+    Bool,
+    // address, bytes, big-endian uint256/int256, hash of indexed dynamic value
+    Binary,
+    // string, arrays and tuples as text
+    Utf8,
+}
+
+impl ColumnType {
+    // Type of value as decoded by ethabi, indexed dynamic values are topic hashes
+    fn of(kind: &ParamType, indexed: bool) -> Self {
+        match kind {
+            ParamType::String | ParamType::Bytes | ParamType::Array(_) | ParamType::FixedArray(..) | ParamType::Tuple(_)
+                if indexed =>
+            {
+                ColumnType::Binary
+            }
+            ParamType::Uint(n) if *n < 64 => ColumnType::Int64,
+            ParamType::Int(n) if *n <= 64 => ColumnType::Int64,
+            ParamType::Bool => ColumnType::Bool,
+            ParamType::Address | ParamType::Bytes | ParamType::FixedBytes(_) | ParamType::Uint(_) | ParamType::Int(_) => {
+                ColumnType::Binary
+            }
+            ParamType::String | ParamType::Array(_) | ParamType::FixedArray(..) | ParamType::Tuple(_) => {
+                ColumnType::Utf8
+            }
+        }
+    }
+
+    fn parquet_type(&self) -> &'static str {
+        match self {
+            ColumnType::Int64 => "INT64",
+            ColumnType::Bool => "BOOLEAN",
+            ColumnType::Binary => "BYTE_ARRAY",
+            ColumnType::Utf8 => "BYTE_ARRAY",
+        }
+    }
+}
+
+// Decoded log of event
+#[derive(Debug, Clone, PartialEq)]
+pub struct DecodedLog {
+    // index of event in decoder
+    pub event: usize,
+    // in column order
+    pub values: Vec<Token>,
+}
+
+pub struct EventSchema {
+    pub event: Event,
+    // unique among events of job, part of file name
+    pub name: String,
+    pub columns: Vec<(String, ColumnType)>,
+    topics_len: usize,
+}
+
+impl EventSchema {
+    fn new(mut event: Event, name: String) -> Self {
+        let mut used: HashSet<String> = LOG_COLUMNS.iter().map(|c| c.to_string()).collect();
+        let mut columns = Vec::with_capacity(event.inputs.len());
+        for (i, input) in event.inputs.iter_mut().enumerate() {
+            let mut column = sanitize(&input.name);
+            if column.is_empty() || !used.insert(column.clone()) {
+                column = format!("arg{}", i);
+                used.insert(column.clone());
+            }
+            // ethabi matches values by param name
+            input.name = column.clone();
+            columns.push((column, ColumnType::of(&input.kind, input.indexed)));
+        }
+        let topics_len = 1 + event.inputs.iter().filter(|input| input.indexed).count();
+        EventSchema {
+            event,
+            name,
+            columns,
+            topics_len,
+        }
+    }
+
+    // Parquet schema: log columns then event params
+    pub fn message_type(&self) -> String {
+        let mut message = format!(
+            "message {} {{ REQUIRED INT64 block_n; REQUIRED INT32 tx_n; REQUIRED BYTE_ARRAY contract;",
+            self.name
+        );
+        for (column, column_type) in &self.columns {
+            let logical = if *column_type == ColumnType::Utf8 {
+                " (UTF8)"
+            } else {
+                ""
+            };
+            message.push_str(&format!(" REQUIRED {} {}{};", column_type.parquet_type(), column, logical));
+        }
+        message.push_str(" }");
+        message
+    }
+}
+
+// Decode matched logs by ABI events, selected by topic0 and topics count
+pub struct EventDecoder {
+    events: Vec<EventSchema>,
+    by_topic0: HashMap<H256, Vec<usize>>,
+}
+
+impl EventDecoder {
+    pub fn new(spec: &DecodeSpec) -> Result<Self> {
+        // file name, event
+        let mut events: Vec<(String, Event)> = Vec::new();
+        if let Some(abi) = &spec.abi {
+            let abi: Abi = serde_json::from_value(abi.clone()).map_err(|err| anyhow!("Invalid abi {}", err))?;
+            events.extend(abi.events().map(|event| (event.name.clone(), event.clone())));
+        }
+        for (i, signature) in spec.events.iter().enumerate() {
+            // known event name or human readable signature
+            if let Some(known) = registry().get(signature) {
+                events.push((known.name.to_string(), known.event.clone()));
+                continue;
+            }
+            let event = HumanReadableParser::parse_event(signature)
+                .map_err(|err| anyhow!("Invalid event signature {}: {}", i, err))?;
+            events.push((event.name.clone(), event));
+        }
+        if events.is_empty() {
+            bail!("No events to decode");
+        }
+        let mut names = HashSet::new();
+        let mut schemas: Vec<EventSchema> = Vec::with_capacity(events.len());
+        let mut by_topic0: HashMap<H256, Vec<usize>> = HashMap::new();
+        for (name, event) in events {
+            if event.anonymous {
+                bail!("Anonymous event {} not supported", event.name);
+            }
+            let signature = event.signature();
+            // same event in abi and signatures
+            if by_topic0
+                .get(&signature)
+                .is_some_and(|i| i.iter().any(|i| same_params(&schemas[*i].event, &event)))
+            {
+                continue;
+            }
+            let mut name = sanitize(&name);
+            if !names.insert(name.clone()) {
+                name = format!("{}_{}", name, schemas.len());
+                names.insert(name.clone());
+            }
+            by_topic0.entry(signature).or_default().push(schemas.len());
+            schemas.push(EventSchema::new(event, name));
+        }
+        Ok(EventDecoder {
+            events: schemas,
+            by_topic0,
+        })
+    }
+
+    pub fn events(&self) -> &[EventSchema] {
+        &self.events
+    }
+
+    // None when no event matches or log is malformed
+    pub fn decode(&self, log: &KvLog) -> Option<DecodedLog> {
+        let candidates = self.by_topic0.get(log.topics.first()?)?;
+        candidates.iter().find_map(|i| {
+            let schema = &self.events[*i];
+            if schema.topics_len != log.topics.len() {
+                return None;
+            }
+            let raw = RawLog {
+                topics: log.topics.clone(),
+                data: log.data.as_ref().map(|d| d.to_vec()).unwrap_or_default(),
+            };
+            let decoded = schema.event.parse_log(raw).ok()?;
+            Some(DecodedLog {
+                event: *i,
+                values: decoded.params.into_iter().map(|p| p.value).collect(),
+            })
+        })
+    }
+}
+
+// Param names ignored
+fn same_params(a: &Event, b: &Event) -> bool {
+    a.inputs.len() == b.inputs.len()
+        && a.inputs
+            .iter()
+            .zip(&b.inputs)
+            .all(|(a, b)| a.kind == b.kind && a.indexed == b.indexed)
+}
+
+// Parquet column and file name: ascii alphanumeric and underscore
+fn sanitize(name: &str) -> String {
+    name.chars()
+        .map(|c| if c.is_ascii_alphanumeric() || c == '_' { c } else { '_' })
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use crate::decode::{ColumnType, EventDecoder};
+    use crate::job::spec::DecodeSpec;
+    use crate::types::KvLog;
+    use bytes::Bytes;
+    use ethers::abi::Token;
+    use ethers::types::{Address, H256, U256};
+
+    #[test]
+    fn decode_transfer() {
+        let abi = serde_json::json!([{
+            "type": "event",
+            "name": "Transfer",
+            "anonymous": false,
+            "inputs": [
+                {"name": "from", "type": "address", "indexed": true},
+                {"name": "to", "type": "address", "indexed": true},
+                {"name": "value", "type": "uint256", "indexed": false}
+            ]
+        }]);
+        let decoder = EventDecoder::new(&DecodeSpec {
+            abi: Some(abi),
+            events: vec![
+                // ERC-721 Transfer: same topic0, tokenId indexed
+                "event Transfer(address indexed from, address indexed to, uint256 indexed tokenId)".to_string(),
+                "event Deposit(address indexed, uint8, uint8, string memo)".to_string(),
+                // same as abi event
+                "ERC20.Transfer".to_string(),
+                "UniswapV2.Sync".to_string(),
+            ],
+        })
+        .unwrap();
+        let names: Vec<&str> = decoder.events().iter().map(|e| e.name.as_str()).collect();
+        assert_eq!(names, vec!["Transfer", "Transfer_1", "Deposit", "UniswapV2_Sync"]);
+        let deposit = &decoder.events()[2];
+        assert_eq!(
+            deposit.columns,
+            vec![
+                ("arg0".to_string(), ColumnType::Binary),
+                ("arg1".to_string(), ColumnType::Int64),
+                ("arg2".to_string(), ColumnType::Int64),
+                ("memo".to_string(), ColumnType::Utf8),
+            ]
+        );
+        assert!(deposit.message_type().contains("REQUIRED BYTE_ARRAY memo (UTF8);"));
+
+        let from = Address::from_low_u64_be(1);
+        let to = Address::from_low_u64_be(2);
+        let topic0 = decoder.events()[0].event.signature();
+        let mut log = KvLog {
+            address: Address::from_low_u64_be(10),
+            topics: vec![topic0, H256::from(from), H256::from(to)],
+            data: Some(Bytes::from(H256::from_low_u64_be(1_000).as_bytes().to_vec())),
+        };
+        let decoded = decoder.decode(&log).unwrap();
+        assert_eq!(decoded.event, 0);
+        assert_eq!(
+            decoded.values,
+            vec![Token::Address(from), Token::Address(to), Token::Uint(U256::from(1_000))]
+        );
+
+        log.topics.push(H256::from_low_u64_be(7));
+        log.data = None;
+        let decoded = decoder.decode(&log).unwrap();
+        assert_eq!(decoded.event, 1);
+        assert_eq!(decoded.values[2], Token::Uint(U256::from(7)));
+
+        // ERC-20 Transfer without value
+        log.topics.pop();
+        assert!(decoder.decode(&log).is_none());
+        log.topics[0] = H256::zero();
+        assert!(decoder.decode(&log).is_none());
+
+        assert!(EventDecoder::new(&DecodeSpec::default()).is_err());
+        assert!(EventDecoder::new(&DecodeSpec {
+            abi: None,
+            events: vec!["event Transfer(address from".to_string()],
+        })
+        .is_err());
+    }
+}
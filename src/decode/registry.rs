@@ -0,0 +1,152 @@
+use crate::types::KvLog;
+use ethers::abi::{Event, HumanReadableParser};
+use ethers::types::H256;
+use std::collections::HashMap;
+use std::sync::OnceLock;
+
+// Events of common standards by <standard>.<event> name.
+// Same signature under several names resolves to the first one
+const KNOWN_EVENTS: [(&str, &str); 22] = [
+    ("ERC20.Transfer", "event Transfer(address indexed from, address indexed to, uint256 value)"),
+    ("ERC20.Approval", "event Approval(address indexed owner, address indexed spender, uint256 value)"),
+    ("ERC721.Transfer", "event Transfer(address indexed from, address indexed to, uint256 indexed tokenId)"),
+    ("ERC721.Approval", "event Approval(address indexed owner, address indexed approved, uint256 indexed tokenId)"),
+    ("ERC721.ApprovalForAll", "event ApprovalForAll(address indexed owner, address indexed operator, bool approved)"),
+    (
+        "ERC1155.TransferSingle",
+        "event TransferSingle(address indexed operator, address indexed from, address indexed to, uint256 id, uint256 value)",
+    ),
+    (
+        "ERC1155.TransferBatch",
+        "event TransferBatch(address indexed operator, address indexed from, address indexed to, uint256[] ids, uint256[] values)",
+    ),
+    ("ERC1155.ApprovalForAll", "event ApprovalForAll(address indexed account, address indexed operator, bool approved)"),
+    ("ERC1155.URI", "event URI(string value, uint256 indexed id)"),
+    ("WETH.Deposit", "event Deposit(address indexed dst, uint256 wad)"),
+    ("WETH.Withdrawal", "event Withdrawal(address indexed src, uint256 wad)"),
+    ("UniswapV2.PairCreated", "event PairCreated(address indexed token0, address indexed token1, address pair, uint256 pairIndex)"),
+    ("UniswapV2.Mint", "event Mint(address indexed sender, uint256 amount0, uint256 amount1)"),
+    ("UniswapV2.Burn", "event Burn(address indexed sender, uint256 amount0, uint256 amount1, address indexed to)"),
+    (
+        "UniswapV2.Swap",
+        "event Swap(address indexed sender, uint256 amount0In, uint256 amount1In, uint256 amount0Out, uint256 amount1Out, address indexed to)",
+    ),
+    ("UniswapV2.Sync", "event Sync(uint112 reserve0, uint112 reserve1)"),
+    (
+        "UniswapV3.PoolCreated",
+        "event PoolCreated(address indexed token0, address indexed token1, uint24 indexed fee, int24 tickSpacing, address pool)",
+    ),
+    ("UniswapV3.Initialize", "event Initialize(uint160 sqrtPriceX96, int24 tick)"),
+    (
+        "UniswapV3.Mint",
+        "event Mint(address sender, address indexed owner, int24 indexed tickLower, int24 indexed tickUpper, uint128 amount, uint256 amount0, uint256 amount1)",
+    ),
+    (
+        "UniswapV3.Burn",
+        "event Burn(address indexed owner, int24 indexed tickLower, int24 indexed tickUpper, uint128 amount, uint256 amount0, uint256 amount1)",
+    ),
+    (
+        "UniswapV3.Swap",
+        "event Swap(address indexed sender, address indexed recipient, int256 amount0, int256 amount1, uint160 sqrtPriceX96, uint128 liquidity, int24 tick)",
+    ),
+    (
+        "UniswapV3.Collect",
+        "event Collect(address indexed owner, address recipient, int24 indexed tickLower, int24 indexed tickUpper, uint128 amount0, uint128 amount1)",
+    ),
+];
+
+pub struct KnownEvent {
+    pub name: &'static str,
+    pub event: Event,
+    pub topic0: H256,
+    // topic0 and indexed params
+    pub topics_len: usize,
+}
+
+pub struct SignatureRegistry {
+    events: Vec<KnownEvent>,
+    by_name: HashMap<&'static str, usize>,
+    // topic0, topics count -> first event registered
+    by_topics: HashMap<(H256, usize), usize>,
+}
+
+impl SignatureRegistry {
+    fn new() -> Self {
+        let mut registry = SignatureRegistry {
+            events: Vec::with_capacity(KNOWN_EVENTS.len()),
+            by_name: HashMap::new(),
+            by_topics: HashMap::new(),
+        };
+        for (name, signature) in KNOWN_EVENTS {
+            let event = HumanReadableParser::parse_event(signature).expect("valid known event signature");
+            let known = KnownEvent {
+                name,
+                topic0: event.signature(),
+                topics_len: 1 + event.inputs.iter().filter(|input| input.indexed).count(),
+                event,
+            };
+            let i = registry.events.len();
+            registry.by_name.insert(name, i);
+            registry.by_topics.entry((known.topic0, known.topics_len)).or_insert(i);
+            registry.events.push(known);
+        }
+        registry
+    }
+
+    pub fn get(&self, name: &str) -> Option<&KnownEvent> {
+        self.by_name.get(name).map(|i| &self.events[*i])
+    }
+
+    // Event of log by topic0 and topics count
+    pub fn resolve(&self, log: &KvLog) -> Option<&KnownEvent> {
+        let topic0 = log.topics.first()?;
+        self.by_topics
+            .get(&(*topic0, log.topics.len()))
+            .map(|i| &self.events[*i])
+    }
+}
+
+pub fn registry() -> &'static SignatureRegistry {
+    static REGISTRY: OnceLock<SignatureRegistry> = OnceLock::new();
+    REGISTRY.get_or_init(SignatureRegistry::new)
+}
+
+#[cfg(test)]
+mod test {
+    use crate::decode::registry::{registry, KNOWN_EVENTS};
+    use crate::types::KvLog;
+    use ethers::types::{Address, H256};
+    use std::str::FromStr;
+
+    #[test]
+    fn known_events() {
+        let registry = registry();
+        assert_eq!(registry.events.len(), KNOWN_EVENTS.len());
+        for (name, topic0) in [
+            ("ERC20.Transfer", "0xddf252ad1be2c89b69c2b068fc378daa952ba7f163c4a11628f55a4df523b3ef"),
+            ("ERC1155.TransferSingle", "0xc3d58168c5ae7397731d063d5bbf3d657854427343f4c083240f7aacaa2d0f62"),
+            ("UniswapV2.Swap", "0xd78ad95fa46c994b6551d0da85fc275fe613ce37657fb8d5e3d130840159d822"),
+            ("UniswapV3.Swap", "0xc42079f94a6350d7e6235f29174924f928cc2ac818eb64fed8004e115fbcca67"),
+        ] {
+            assert_eq!(registry.get(name).unwrap().topic0, H256::from_str(topic0).unwrap());
+        }
+        assert!(registry.get("ERC20.Mint").is_none());
+
+        let topic0 = registry.get("ERC20.Transfer").unwrap().topic0;
+        let mut log = KvLog {
+            address: Address::zero(),
+            topics: vec![topic0, H256::zero(), H256::zero()],
+            data: None,
+        };
+        assert_eq!(registry.resolve(&log).unwrap().name, "ERC20.Transfer");
+        log.topics.push(H256::zero());
+        assert_eq!(registry.resolve(&log).unwrap().name, "ERC721.Transfer");
+        assert_ne!(registry.get("ERC20.Transfer").unwrap().topics_len, log.topics.len());
+        log.topics.push(H256::zero());
+        assert!(registry.resolve(&log).is_none());
+        // identical signature, first name wins
+        let approval_for_all = registry.get("ERC1155.ApprovalForAll").unwrap();
+        log.topics = vec![approval_for_all.topic0, H256::zero(), H256::zero()];
+        assert_eq!(registry.resolve(&log).unwrap().name, "ERC721.ApprovalForAll");
+    }
+}
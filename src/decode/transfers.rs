@@ -0,0 +1,140 @@
+use crate::decode::registry::registry;
+use crate::types::KvLog;
+use ethers::abi::{RawLog, Token};
+use ethers::types::{Address, U256};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TokenStandard {
+    Erc20,
+    Erc721,
+    Erc1155,
+}
+
+impl TokenStandard {
+    pub fn name(&self) -> &'static str {
+        match self {
+            TokenStandard::Erc20 => "ERC20",
+            TokenStandard::Erc721 => "ERC721",
+            TokenStandard::Erc1155 => "ERC1155",
+        }
+    }
+}
+
+// Normalized transfer of fungible or non-fungible token
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TokenTransfer {
+    pub standard: TokenStandard,
+    pub token: Address,
+    pub from: Address,
+    pub to: Address,
+    // None for ERC-20
+    pub token_id: Option<U256>,
+    // 1 for ERC-721
+    pub amount: U256,
+}
+
+// Transfers of standard transfer event, one per id of ERC-1155 batch.
+// Empty for other or malformed logs
+pub fn token_transfers(log: &KvLog) -> Vec<TokenTransfer> {
+    let Some(known) = registry().resolve(log) else {
+        return Vec::new();
+    };
+    let standard = match known.name {
+        "ERC20.Transfer" => TokenStandard::Erc20,
+        "ERC721.Transfer" => TokenStandard::Erc721,
+        "ERC1155.TransferSingle" | "ERC1155.TransferBatch" => TokenStandard::Erc1155,
+        _ => return Vec::new(),
+    };
+    let raw = RawLog {
+        topics: log.topics.clone(),
+        data: log.data.as_ref().map(|d| d.to_vec()).unwrap_or_default(),
+    };
+    let Ok(decoded) = known.event.parse_log(raw) else {
+        return Vec::new();
+    };
+    let values: Vec<Token> = decoded.params.into_iter().map(|p| p.value).collect();
+    let transfer = |from: &Token, to: &Token, token_id: Option<U256>, amount: U256| {
+        Some(TokenTransfer {
+            standard,
+            token: log.address,
+            from: from.clone().into_address()?,
+            to: to.clone().into_address()?,
+            token_id,
+            amount,
+        })
+    };
+    match (standard, values.as_slice()) {
+        (TokenStandard::Erc20, [from, to, Token::Uint(amount)]) => transfer(from, to, None, *amount).into_iter().collect(),
+        (TokenStandard::Erc721, [from, to, Token::Uint(token_id)]) => {
+            transfer(from, to, Some(*token_id), U256::one()).into_iter().collect()
+        }
+        (TokenStandard::Erc1155, [_, from, to, Token::Uint(id), Token::Uint(amount)]) => {
+            transfer(from, to, Some(*id), *amount).into_iter().collect()
+        }
+        (TokenStandard::Erc1155, [_, from, to, Token::Array(ids), Token::Array(amounts)]) if ids.len() == amounts.len() => ids
+            .iter()
+            .zip(amounts)
+            .map(|(id, amount)| transfer(from, to, id.clone().into_uint(), amount.clone().into_uint()?))
+            .collect::<Option<Vec<_>>>()
+            .unwrap_or_default(),
+        _ => Vec::new(),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::decode::registry::registry;
+    use crate::decode::transfers::{token_transfers, TokenStandard};
+    use crate::types::KvLog;
+    use bytes::Bytes;
+    use ethers::abi::{encode, Token};
+    use ethers::types::{Address, H256, U256};
+
+    #[test]
+    fn transfers() {
+        let token = Address::from_low_u64_be(100);
+        let from = Address::from_low_u64_be(1);
+        let to = Address::from_low_u64_be(2);
+        let topic0 = |name: &str| registry().get(name).unwrap().topic0;
+        let mut log = KvLog {
+            address: token,
+            topics: vec![topic0("ERC20.Transfer"), H256::from(from), H256::from(to)],
+            data: Some(Bytes::from(encode(&[Token::Uint(U256::from(500))]))),
+        };
+        let transfers = token_transfers(&log);
+        assert_eq!(transfers.len(), 1);
+        assert_eq!(transfers[0].standard, TokenStandard::Erc20);
+        assert_eq!((transfers[0].token, transfers[0].from, transfers[0].to), (token, from, to));
+        assert_eq!((transfers[0].token_id, transfers[0].amount), (None, U256::from(500)));
+
+        // ERC-721: token id indexed
+        log.topics.push(H256::from_low_u64_be(7));
+        log.data = None;
+        let transfers = token_transfers(&log);
+        assert_eq!(transfers[0].standard, TokenStandard::Erc721);
+        assert_eq!((transfers[0].token_id, transfers[0].amount), (Some(U256::from(7)), U256::one()));
+
+        let operator = H256::from(Address::from_low_u64_be(3));
+        log.topics = vec![topic0("ERC1155.TransferBatch"), operator, H256::from(from), H256::from(to)];
+        log.data = Some(Bytes::from(encode(&[
+            Token::Array(vec![Token::Uint(U256::from(1)), Token::Uint(U256::from(2))]),
+            Token::Array(vec![Token::Uint(U256::from(10)), Token::Uint(U256::from(20))]),
+        ])));
+        let transfers = token_transfers(&log);
+        let items: Vec<_> = transfers.iter().map(|t| (t.standard, t.token_id, t.amount)).collect();
+        assert_eq!(
+            items,
+            vec![
+                (TokenStandard::Erc1155, Some(U256::from(1)), U256::from(10)),
+                (TokenStandard::Erc1155, Some(U256::from(2)), U256::from(20))
+            ]
+        );
+
+        // malformed data, other event
+        log.data = None;
+        assert!(token_transfers(&log).is_empty());
+        log.topics = vec![topic0("ERC20.Approval"), H256::from(from), H256::from(to)];
+        log.data = Some(Bytes::from(encode(&[Token::Uint(U256::from(500))])));
+        assert!(token_transfers(&log).is_empty());
+    }
+}
@@ -0,0 +1,118 @@
+use crate::engine::scan::{LogScan, Progress};
+use crate::engine::{SearchEngine, LOG_CHUNK_SIZE};
+use crate::job::spec::{AggregateSpec, GroupBy, JobSpec};
+use crate::storage::table::ColumnData;
+use crate::storage::WriterOptions;
+use anyhow::{anyhow, Result};
+use ethers::types::{Address, H256};
+use log::info;
+use parquet::data_type::ByteArray;
+use std::collections::HashMap;
+use std::path::Path;
+
+// Values of group-by columns, None when not grouped by column
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+struct GroupKey {
+    contract: Option<Address>,
+    // log without topics: Some(None)
+    topic0: Option<Option<H256>>,
+    block: Option<u64>,
+}
+
+#[derive(Default)]
+struct GroupStats {
+    count: u64,
+    data_bytes: u64,
+}
+
+impl SearchEngine {
+    // Write log count and data bytes of matched logs by group, ordered by group. Return groups count
+    pub(super) fn aggregate_logs(
+        &self,
+        job_id: &str,
+        result_file: &Path,
+        spec: &JobSpec,
+        options: &WriterOptions,
+        scan: LogScan<'_>,
+        progress: &Progress,
+    ) -> Result<u64> {
+        let aggregate = spec
+            .aggregate
+            .as_ref()
+            .ok_or_else(|| anyhow!("Aggregate job without group-by"))?;
+        let by = |group_by| aggregate.group_by.contains(&group_by);
+        let (by_contract, by_topic0, by_block) = (by(GroupBy::Contract), by(GroupBy::Topic0), by(GroupBy::Block));
+        let mut groups: HashMap<GroupKey, GroupStats> = HashMap::new();
+        let mut log_count: u64 = 0;
+        for tx in scan {
+            let tx = tx?;
+            progress.update(self, job_id, tx.block_n, log_count);
+            let block = by_block.then(|| tx.block_n - tx.block_n % aggregate.block_bucket);
+            for log in &tx.logs {
+                let key = GroupKey {
+                    contract: by_contract.then_some(log.address),
+                    topic0: by_topic0.then(|| log.topics.first().copied()),
+                    block,
+                };
+                let stats = groups.entry(key).or_default();
+                stats.count += 1;
+                stats.data_bytes += log.data.as_ref().map_or(0, |d| d.len() as u64);
+            }
+            log_count += tx.logs.len() as u64;
+        }
+        info!("[{}] Logs:{} groups:{}", job_id, log_count, groups.len());
+
+        let mut rows: Vec<(GroupKey, GroupStats)> = groups.into_iter().collect();
+        rows.sort_by_key(|(key, _)| *key);
+        let mut writer = self.result_storage.create_table(
+            job_id,
+            result_file,
+            &message_type(aggregate),
+            options,
+        )?;
+        for chunk in rows.chunks(LOG_CHUNK_SIZE) {
+            let mut columns = Vec::new();
+            if by_contract {
+                columns.push(ColumnData::Bytes(
+                    chunk
+                        .iter()
+                        .map(|(key, _)| ByteArray::from(key.contract.unwrap_or_default().as_bytes()))
+                        .collect(),
+                ));
+            }
+            if by_topic0 {
+                columns.push(ColumnData::optional_bytes(
+                    chunk
+                        .iter()
+                        .map(|(key, _)| key.topic0.flatten().map(|t| ByteArray::from(t.as_bytes()))),
+                ));
+            }
+            if by_block {
+                columns.push(ColumnData::Int64(
+                    chunk.iter().map(|(key, _)| key.block.unwrap_or_default() as i64).collect(),
+                ));
+            }
+            columns.push(ColumnData::Int64(chunk.iter().map(|(_, s)| s.count as i64).collect()));
+            columns.push(ColumnData::Int64(chunk.iter().map(|(_, s)| s.data_bytes as i64).collect()));
+            writer.write_row_group(columns)?;
+        }
+        writer.publish()?;
+        Ok(rows.len() as u64)
+    }
+}
+
+// Group-by columns in contract, topic0, block order, then aggregates
+fn message_type(aggregate: &AggregateSpec) -> String {
+    let mut message = "message schema {".to_string();
+    for (group_by, column) in [
+        (GroupBy::Contract, " REQUIRED BYTE_ARRAY contract;"),
+        (GroupBy::Topic0, " OPTIONAL BYTE_ARRAY topic0;"),
+        (GroupBy::Block, " REQUIRED INT64 block_n;"),
+    ] {
+        if aggregate.group_by.contains(&group_by) {
+            message.push_str(column);
+        }
+    }
+    message.push_str(" REQUIRED INT64 count; REQUIRED INT64 data_bytes; }");
+    message
+}
@@ -0,0 +1,99 @@
+use crate::db::block::{read_header, Header};
+use crate::db::chain::decode_canonical;
+use crate::db::CANONICAL_HEADER_TABLE;
+use crate::engine::scan::{block_cursor, Progress};
+use crate::engine::{SearchEngine, LOG_CHUNK_SIZE};
+use crate::storage::table::{uint256_value, ColumnData, TableWriter};
+use crate::storage::WriterOptions;
+use anyhow::{anyhow, Result};
+use ethers::types::H256;
+use libmdbx::{NoWriteMap, Transaction, RO};
+use log::info;
+use parquet::data_type::ByteArray;
+use std::path::Path;
+
+const BLOCKS_MESSAGE_TYPE: &str = "
+              message blocks {
+                REQUIRED INT64 block_n;
+                REQUIRED BYTE_ARRAY hash;
+                REQUIRED BYTE_ARRAY parent_hash;
+                REQUIRED INT64 timestamp;
+                REQUIRED BYTE_ARRAY miner;
+                REQUIRED BYTE_ARRAY state_root;
+                REQUIRED INT64 gas_limit;
+                REQUIRED INT64 gas_used;
+                OPTIONAL BYTE_ARRAY base_fee_per_gas;
+                REQUIRED BYTE_ARRAY difficulty;
+                REQUIRED BYTE_ARRAY extra_data;
+                OPTIONAL BYTE_ARRAY withdrawals_root;
+                OPTIONAL INT64 blob_gas_used;
+                OPTIONAL INT64 excess_blob_gas;
+                OPTIONAL BYTE_ARRAY parent_beacon_block_root;
+              }
+";
+
+impl SearchEngine {
+    // Write canonical headers in range. Return blocks count
+    pub(super) fn export_blocks(
+        &self,
+        job_id: &str,
+        result_file: &Path,
+        options: &WriterOptions,
+        txn: &Transaction<RO, NoWriteMap>,
+        (from_block, to_block): (u64, u64),
+        progress: &Progress,
+    ) -> Result<u64> {
+        let mut writer = self.result_storage.create_table(
+            job_id,
+            result_file,
+            BLOCKS_MESSAGE_TYPE,
+            options,
+        )?;
+        let mut block_count: u64 = 0;
+        let mut chunk = Vec::with_capacity(LOG_CHUNK_SIZE);
+        for item in block_cursor(txn, CANONICAL_HEADER_TABLE, from_block)? {
+            let (key, hash) = item?;
+            let (block_n, hash) = decode_canonical(&key, &hash)?;
+            if block_n > to_block {
+                break;
+            }
+            progress.update(self, job_id, block_n, block_count);
+            let header =
+                read_header(txn, block_n, &hash)?.ok_or_else(|| anyhow!("Missing header of block {}", block_n))?;
+            chunk.push((hash, header));
+            if chunk.len() > LOG_CHUNK_SIZE {
+                block_count += chunk.len() as u64;
+                info!("[{}] block:{} blocks count:{}", job_id, block_n, block_count);
+                write_blocks(&mut writer, &chunk)?;
+                chunk.clear();
+            }
+        }
+        if !chunk.is_empty() {
+            block_count += chunk.len() as u64;
+            write_blocks(&mut writer, &chunk)?;
+        }
+        writer.publish()?;
+        Ok(block_count)
+    }
+}
+
+fn write_blocks(writer: &mut TableWriter, chunk: &[(H256, Header)]) -> Result<()> {
+    let hash = |h: &H256| ByteArray::from(h.as_bytes());
+    writer.write_row_group(vec![
+        ColumnData::Int64(chunk.iter().map(|(_, h)| h.number as i64).collect()),
+        ColumnData::Bytes(chunk.iter().map(|(block_hash, _)| hash(block_hash)).collect()),
+        ColumnData::Bytes(chunk.iter().map(|(_, h)| hash(&h.parent_hash)).collect()),
+        ColumnData::Int64(chunk.iter().map(|(_, h)| h.timestamp as i64).collect()),
+        ColumnData::Bytes(chunk.iter().map(|(_, h)| ByteArray::from(h.miner.as_bytes())).collect()),
+        ColumnData::Bytes(chunk.iter().map(|(_, h)| hash(&h.state_root)).collect()),
+        ColumnData::Int64(chunk.iter().map(|(_, h)| h.gas_limit as i64).collect()),
+        ColumnData::Int64(chunk.iter().map(|(_, h)| h.gas_used as i64).collect()),
+        ColumnData::optional_bytes(chunk.iter().map(|(_, h)| h.base_fee_per_gas.map(uint256_value))),
+        ColumnData::Bytes(chunk.iter().map(|(_, h)| uint256_value(h.difficulty)).collect()),
+        ColumnData::Bytes(chunk.iter().map(|(_, h)| ByteArray::from(h.extra_data.clone())).collect()),
+        ColumnData::optional_bytes(chunk.iter().map(|(_, h)| h.withdrawals_root.as_ref().map(hash))),
+        ColumnData::optional_int64(chunk.iter().map(|(_, h)| h.blob_gas_used.map(|g| g as i64))),
+        ColumnData::optional_int64(chunk.iter().map(|(_, h)| h.excess_blob_gas.map(|g| g as i64))),
+        ColumnData::optional_bytes(chunk.iter().map(|(_, h)| h.parent_beacon_block_root.as_ref().map(hash))),
+    ])
+}
@@ -0,0 +1,100 @@
+use crate::decode::registry::registry;
+use crate::engine::scan::{LogScan, Progress};
+use crate::engine::SearchEngine;
+use crate::storage::table::ColumnData;
+use crate::storage::WriterOptions;
+use crate::types::KvLog;
+use anyhow::Result;
+use ethers::types::{Address, H256};
+use log::info;
+use parquet::data_type::ByteArray;
+use std::collections::HashMap;
+use std::path::Path;
+
+const DISCOVERY_MESSAGE_TYPE: &str = "
+              message schema {
+                REQUIRED BYTE_ARRAY contract;
+                OPTIONAL BYTE_ARRAY topic0;
+                REQUIRED INT32 op_code;
+                REQUIRED INT64 count;
+                REQUIRED INT64 first_block;
+                REQUIRED INT64 last_block;
+                OPTIONAL BYTE_ARRAY event_name (UTF8);
+              }
+";
+
+// contract, topic0, topics count
+type EventKey = (Address, Option<H256>, usize);
+
+struct EventStats {
+    count: u64,
+    first_block: u64,
+    last_block: u64,
+}
+
+impl SearchEngine {
+    // Write (contract, topic0, op_code) counts of matched logs, most frequent first. Return summary rows count
+    pub(super) fn discover_events(
+        &self,
+        job_id: &str,
+        result_file: &Path,
+        options: &WriterOptions,
+        scan: LogScan<'_>,
+        progress: &Progress,
+    ) -> Result<u64> {
+        let mut stats: HashMap<EventKey, EventStats> = HashMap::new();
+        let mut log_count: u64 = 0;
+        for tx in scan {
+            let tx = tx?;
+            progress.update(self, job_id, tx.block_n, log_count);
+            for log in &tx.logs {
+                stats
+                    .entry((log.address, log.topics.first().copied(), log.topics.len()))
+                    .and_modify(|s| {
+                        s.count += 1;
+                        s.last_block = tx.block_n;
+                    })
+                    .or_insert(EventStats {
+                        count: 1,
+                        first_block: tx.block_n,
+                        last_block: tx.block_n,
+                    });
+            }
+            log_count += tx.logs.len() as u64;
+        }
+        info!("[{}] Logs:{} events discovered:{}", job_id, log_count, stats.len());
+
+        let mut rows: Vec<(EventKey, EventStats)> = stats.into_iter().collect();
+        rows.sort_by(|(a_key, a), (b_key, b)| b.count.cmp(&a.count).then(a_key.cmp(b_key)));
+        let mut writer = self.result_storage.create_table(
+            job_id,
+            result_file,
+            DISCOVERY_MESSAGE_TYPE,
+            options,
+        )?;
+        if !rows.is_empty() {
+            writer.write_row_group(vec![
+                ColumnData::Bytes(rows.iter().map(|((c, _, _), _)| ByteArray::from(c.as_bytes())).collect()),
+                ColumnData::optional_bytes(rows.iter().map(|((_, t, _), _)| t.map(|t| ByteArray::from(t.as_bytes())))),
+                ColumnData::Int32(rows.iter().map(|((_, _, op_code), _)| *op_code as i32).collect()),
+                ColumnData::Int64(rows.iter().map(|(_, s)| s.count as i64).collect()),
+                ColumnData::Int64(rows.iter().map(|(_, s)| s.first_block as i64).collect()),
+                ColumnData::Int64(rows.iter().map(|(_, s)| s.last_block as i64).collect()),
+                ColumnData::optional_bytes(rows.iter().map(|(key, _)| event_name(key).map(ByteArray::from))),
+            ])?;
+        }
+        writer.publish()?;
+        Ok(rows.len() as u64)
+    }
+}
+
+// Known event name by topic0 and topics count
+fn event_name(&(address, topic0, op_code): &EventKey) -> Option<&'static str> {
+    let topic0 = topic0?;
+    let log = KvLog {
+        address,
+        topics: vec![topic0; op_code],
+        data: None,
+    };
+    registry().resolve(&log).map(|e| e.name)
+}
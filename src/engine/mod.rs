@@ -1,152 +1,1493 @@
-use crate::storage::ResultStorage;
-use crate::types::{KvLog, LogChunk};
-use anyhow::Result;
-use byteorder::{BigEndian, ByteOrder};
-use ethers::abi::Address;
-use futures::join;
-use libmdbx::{Environment, EnvironmentFlags, Mode, NoWriteMap};
-use log::{error, info};
-use std::path::PathBuf;
-use std::sync::Arc;
-use tokio::time::Instant;
+mod aggregate;
+mod blocks;
+mod discovery;
+mod receipts;
+mod scan;
+mod state_diffs;
+mod transactions;
+mod transfers;
+mod withdrawals;
+
+use crate::db;
+use crate::db::chain::ChainStatus;
+use crate::engine::scan::{LogScan, Progress};
+use crate::job::events::{JobEvent, JobEvents};
+use crate::job::queue::JobQueue;
+use crate::job::spec::{BlockTag, Finality, JobKind, JobSpec};
+use crate::job::{JobInfo, JobRegistry, RegisterError};
+use crate::metrics::Metrics;
+use crate::storage::{ResultStorage, WriterOptions};
+use crate::types::LogChunk;
+use crate::webhook::WebhookNotifier;
+use anyhow::{anyhow, bail, Result};
+use ethers::types::H256;
+use libmdbx::{Environment, NoWriteMap, Transaction, RO};
+use log::{error, info, warn};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::mpsc::Sender;
+use tokio::sync::{broadcast, Notify, OwnedSemaphorePermit};
+use tokio::task::{spawn_blocking, JoinError, JoinHandle};
+use tokio::time::sleep;
 
 pub struct SearchEngine {
     db_path: PathBuf,
+    // read-only env shared by requests and jobs, opened on first use
+    db_env: Mutex<Option<Arc<Environment<NoWriteMap>>>>,
     result_storage: Arc<ResultStorage>,
+    job_registry: Arc<JobRegistry>,
+    job_queue: JobQueue,
+    webhook: Arc<WebhookNotifier>,
+    events: JobEvents,
+    metrics: Arc<Metrics>,
+    // how often follow jobs check execution stage
+    follow_interval: Duration,
+    // stop signal of running follow jobs
+    follow_stops: Mutex<HashMap<String, Arc<Notify>>>,
 }
 
 pub const LOG_CHUNK_SIZE: usize = 1_000_000;
+// Exported blocks of follow job checked for reorg
+pub const REORG_WINDOW: u64 = 128;
+// Min time between progress events of job
+const PROGRESS_INTERVAL: Duration = Duration::from_secs(1);
 
 impl SearchEngine {
-    pub fn new(db_path: PathBuf, result_storage: Arc<ResultStorage>) -> Self {
+    pub fn new(
+        db_path: PathBuf,
+        result_storage: Arc<ResultStorage>,
+        job_registry: Arc<JobRegistry>,
+        max_concurrent_jobs: usize,
+        webhook: Arc<WebhookNotifier>,
+        metrics: Arc<Metrics>,
+        follow_interval: Duration,
+    ) -> Self {
         info!("Db path:{}", db_path.to_str().unwrap());
+        info!("Max concurrent jobs:{}", max_concurrent_jobs);
+        info!("Follow poll interval:{}s", follow_interval.as_secs());
 
         SearchEngine {
             db_path,
+            db_env: Mutex::new(None),
             result_storage,
+            job_registry,
+            job_queue: JobQueue::new(max_concurrent_jobs),
+            webhook,
+            events: JobEvents::default(),
+            metrics,
+            follow_interval,
+            follow_stops: Mutex::new(HashMap::new()),
+        }
+    }
+
+    // Register job and put it to queue, return job id
+    pub fn submit_job(&self, spec: JobSpec) -> Result<String, RegisterError> {
+        let (to_block, finality) = self.resolve_to_block(&spec)?;
+        let job = self.job_registry.register(spec, to_block, finality)?;
+        let job_id = job.job_id.clone();
+        self.events.open(&job_id);
+        let position = self.job_queue.push(job);
+        info!("[{}] Job queued. Position:{}", job_id, position);
+        Ok(job_id)
+    }
+
+    // Db may be missing at start, open is retried until it succeeds
+    fn db_env(&self) -> Result<Arc<Environment<NoWriteMap>>> {
+        let mut db_env = self.db_env.lock().unwrap();
+        if let Some(env) = db_env.as_ref() {
+            return Ok(env.clone());
+        }
+        let env = Arc::new(db::open_env(&self.db_path)?);
+        *db_env = Some(env.clone());
+        Ok(env)
+    }
+
+    fn read_sync_head(&self) -> Result<db::chain::SyncHead> {
+        let db_env = self.db_env()?;
+        let txn = db_env.begin_ro_txn()?;
+        db::chain::read_sync_head(&txn)
+    }
+
+    fn find_fork(&self, block_hashes: &[(u64, H256)]) -> Result<Option<u64>> {
+        let db_env = self.db_env()?;
+        let txn = db_env.begin_ro_txn()?;
+        db::chain::find_fork(&txn, block_hashes)
+    }
+
+    // Range must be executed by Erigon, otherwise result silently incomplete.
+    // Return to_block and its finality
+    fn resolve_to_block(&self, spec: &JobSpec) -> Result<(u64, Finality), RegisterError> {
+        let head = self.read_sync_head().map_err(|err| RegisterError::DbUnavailable(err.to_string()))?;
+        let executed = head.executed;
+        let forkchoice = |block: Option<u64>| block.ok_or(RegisterError::NoForkchoice(spec.to_block));
+        let to_block = match spec.to_block {
+            BlockTag::Number(n) => n,
+            BlockTag::Latest => executed,
+            BlockTag::Safe => forkchoice(head.safe)?,
+            BlockTag::Finalized => forkchoice(head.finalized)?,
+        };
+        if to_block > executed {
+            return Err(RegisterError::BeyondExecuted {
+                field: "to_block",
+                block: to_block,
+                executed,
+            });
+        }
+        // from_block > to_block rejected by spec validation, only latest left
+        if spec.from_block > to_block {
+            return Err(RegisterError::BeyondExecuted {
+                field: "from_block",
+                block: spec.from_block,
+                executed,
+            });
+        }
+        let finality = match spec.to_block {
+            BlockTag::Safe => Finality::Safe,
+            BlockTag::Finalized => Finality::Finalized,
+            // follow job tails executed blocks
+            _ if spec.follow => Finality::Unfinalized,
+            _ => Finality::of_block(to_block, head.safe, head.finalized),
+        };
+        Ok((to_block, finality))
+    }
+
+    // Queue again jobs which were waiting when app stopped, notify callbacks of interrupted ones
+    pub fn resume_jobs(self: &Arc<Self>) {
+        let (queued, interrupted) = self.job_registry.recover();
+        for job in queued {
+            info!("[{}] Resume queued job", job.job_id);
+            self.events.open(&job.job_id);
+            self.job_queue.push(job);
+        }
+        let engine = self.clone();
+        tokio::spawn(async move {
+            for job in interrupted {
+                engine.notify(&job.job_id).await;
+            }
+        });
+    }
+
+    pub fn queue_position(&self, job_id: &str) -> Option<usize> {
+        self.job_queue.position(job_id)
+    }
+
+    // Db opens and has required tables
+    pub fn check_db(&self) -> Result<()> {
+        db::check_db(&*self.db_env()?)
+    }
+
+    pub fn chain_status(&self) -> Result<ChainStatus> {
+        db::chain::read_chain_status(&*self.db_env()?, &self.db_path)
+    }
+
+    // Ask running follow job to finish after current part, false if no such job
+    pub fn stop_job(&self, job_id: &str) -> bool {
+        match self.follow_stops.lock().unwrap().get(job_id) {
+            Some(stop) => {
+                info!("[{}] Stop follow job", job_id);
+                stop.notify_one();
+                true
+            }
+            None => false,
+        }
+    }
+
+    // Progress events of queued or running job
+    pub fn subscribe(&self, job_id: &str) -> Option<broadcast::Receiver<JobEvent>> {
+        self.events.subscribe(job_id)
+    }
+
+    // Run queued jobs while slots available
+    pub fn start_dispatcher(self: &Arc<Self>) -> JoinHandle<()> {
+        let engine = self.clone();
+        tokio::spawn(async move {
+            loop {
+                let (slot, queued) = engine.job_queue.next().await;
+                let engine = engine.clone();
+                tokio::spawn(async move {
+                    let job = queued.job;
+                    engine.job_registry.start(&job.job_id);
+                    engine.events.publish(
+                        &job.job_id,
+                        JobEvent::Status {
+                            status: "running",
+                            queue_position: None,
+                        },
+                    );
+                    // own task, panicked job fails instead of staying running
+                    let run = {
+                        let engine = engine.clone();
+                        let job = job.clone();
+                        tokio::spawn(async move {
+                            #[cfg(test)]
+                            if job.job_id == test::PANIC_JOB_ID {
+                                panic!("Panic hook");
+                            }
+                            if job.spec.follow {
+                                // follow job releases slot after catch up
+                                (engine.follow_job(&job, slot).await, None)
+                            } else {
+                                let result = engine.execute_job(&job).await;
+                                (result, Some(slot))
+                            }
+                        })
+                    };
+                    let (result, slot) = match run.await {
+                        Ok(done) => done,
+                        Err(err) => {
+                            engine.follow_stops.lock().unwrap().remove(&job.job_id);
+                            (Err(anyhow!("Job panicked: {}", panic_message(err))), None)
+                        }
+                    };
+                    if let Err(err) = &result {
+                        error!("[{}] Job err:{}", job.job_id, err);
+                    }
+                    engine.job_registry.finish(&job.job_id, &result);
+                    if let Some(finished) = engine.job_registry.get(&job.job_id) {
+                        engine.events.close(&job.job_id, JobEvent::from_job(&finished, None));
+                    }
+                    // release slot for next job
+                    drop(slot);
+                    engine.notify(&job.job_id).await;
+                });
+            }
+        })
+    }
+
+    // Send job result to callback url of job spec
+    async fn notify(&self, job_id: &str) {
+        let Some(job) = self.job_registry.get(job_id) else {
+            return;
+        };
+        if let Some(url) = &job.spec.callback_url {
+            if let Err(err) = self.webhook.notify(url, &job).await {
+                error!("[{}] Webhook failed:{}", job_id, err);
+            }
+        }
+    }
+
+    // Catch up to to_block, then write blocks executed by Erigon since last part
+    // to new part files until stopped. Return logs count of all parts
+    async fn follow_job(self: &Arc<Self>, job: &JobInfo, slot: OwnedSemaphorePermit) -> Result<u64> {
+        let job_id = &job.job_id;
+        let stop = Arc::new(Notify::new());
+        self.follow_stops.lock().unwrap().insert(job_id.clone(), stop.clone());
+        let mut slot = Some(slot);
+        let mut rows = job.rows.unwrap_or_default();
+        let mut part = job.parts;
+        // resumed job continues after last written block
+        let mut from_block = job.last_block.map(|b| b + 1).unwrap_or(job.spec.from_block);
+        let mut to_block = job.to_block;
+        let mut block_hashes = job.block_hashes.clone();
+        let result = loop {
+            // Erigon unwound exported blocks, retract them and export canonical ones again
+            match self.find_fork(&block_hashes) {
+                Ok(Some(fork_block)) => {
+                    if let Err(err) = self.retract(job, part, fork_block, &block_hashes) {
+                        break Err(err);
+                    }
+                    part += 1;
+                    block_hashes.retain(|(n, _)| *n < fork_block);
+                    from_block = fork_block;
+                    self.job_registry
+                        .follow_progress(job_id, fork_block.checked_sub(1), part, rows, &block_hashes);
+                    // new branch may be shorter or not executed yet
+                    match self.follow_head(job.finality) {
+                        Ok(head) => to_block = to_block.min(head),
+                        Err(err) => warn!("[{}] Read sync head err:{}", job_id, err),
+                    }
+                }
+                Ok(None) => {}
+                Err(err) => warn!("[{}] Check reorg err:{}", job_id, err),
+            }
+            if from_block <= to_block {
+                let part_file = self.result_storage.part_file(job_id, part);
+                let (part_rows, last_block) = match self
+                    .execute_range(job, part_file, from_block, to_block)
+                    .await
+                {
+                    Ok((part_rows, hashes)) => {
+                        // range stops short at blocks not executed again after unwind
+                        let last_block = hashes.last().map(|(n, _)| *n);
+                        block_hashes.extend(hashes);
+                        let skip = block_hashes.len().saturating_sub(REORG_WINDOW as usize);
+                        block_hashes.drain(..skip);
+                        (part_rows, last_block)
+                    }
+                    Err(err) => break Err(err),
+                };
+                if part_rows == 0 {
+                    if let Err(err) = self.result_storage.discard_part(job_id, part) {
+                        warn!("[{}] Discard empty part err:{}", job_id, err);
+                    }
+                }
+                if let Some(last_block) = last_block {
+                    if part_rows > 0 {
+                        rows += part_rows;
+                        self.events.publish(
+                            job_id,
+                            JobEvent::PartWritten {
+                                part,
+                                from_block,
+                                to_block: last_block,
+                                part_rows,
+                                rows,
+                            },
+                        );
+                        part += 1;
+                    }
+                    self.job_registry
+                        .follow_progress(job_id, Some(last_block), part, rows, &block_hashes);
+                    from_block = last_block + 1;
+                }
+            }
+            // caught up, tail outside of concurrency limit
+            slot.take();
+            tokio::select! {
+                _ = sleep(self.follow_interval) => {}
+                _ = stop.notified() => break Ok(rows),
+            }
+            // Erigon may restart, retry on next poll
+            match self.follow_head(job.finality) {
+                Ok(head) => to_block = head,
+                Err(err) => warn!("[{}] Read sync head err:{}", job_id, err),
+            }
+        };
+        self.follow_stops.lock().unwrap().remove(job_id);
+        result
+    }
+
+    // Highest block of job finality: executed or fork choice block
+    fn follow_head(&self, finality: Finality) -> Result<u64> {
+        let head = self.read_sync_head()?;
+        if finality == Finality::Unfinalized {
+            return Ok(head.executed);
+        }
+        let forkchoice = if finality == Finality::Safe {
+            head.safe
+        } else {
+            head.finalized
+        };
+        // fork choice may be ahead of execution
+        Ok(forkchoice.unwrap_or_default().min(head.executed))
+    }
+
+    // Write retraction part of exported blocks from fork_block
+    fn retract(&self, job: &JobInfo, part: u32, fork_block: u64, block_hashes: &[(u64, H256)]) -> Result<()> {
+        let job_id = &job.job_id;
+        let orphaned: Vec<(u64, H256)> = block_hashes.iter().filter(|(n, _)| *n >= fork_block).copied().collect();
+        warn!("[{}] Reorg at block:{}, retract {} blocks", job_id, fork_block, orphaned.len());
+        if block_hashes.first().is_some_and(|(n, _)| *n == fork_block && fork_block > job.spec.from_block) {
+            warn!("[{}] Reorg may be deeper than {} tracked blocks", job_id, REORG_WINDOW);
+        }
+        self.result_storage.write_retraction(job_id, part, &orphaned)?;
+        self.events.publish(
+            job_id,
+            JobEvent::Reorg {
+                fork_block,
+                retracted_blocks: orphaned.len() as u64,
+                part,
+            },
+        );
+        Ok(())
+    }
+
+    // Return logs count
+    pub async fn execute_job(self: &Arc<Self>, job: &JobInfo) -> Result<u64> {
+        self.execute_range(job, job.result_file.clone(), job.spec.from_block, job.to_block)
+            .await
+            .map(|(rows, _)| rows)
+    }
+
+    // Run job kind over block range and write result file.
+    // Return rows count and, for follow job, canonical hashes of last blocks of range
+    async fn execute_range(
+        self: &Arc<Self>,
+        job: &JobInfo,
+        result_file: PathBuf,
+        from_block: u64,
+        to_block: u64,
+    ) -> Result<(u64, Vec<(u64, H256)>)> {
+        let options = WriterOptions::new(&job.spec, job.finality)?;
+        // scans loop over cursors without await, keep them off async workers
+        let engine = self.clone();
+        let job = job.clone();
+        if job.spec.kind != JobKind::Logs {
+            let rows = spawn_blocking(move || engine.execute_table(&job, &result_file, &options, from_block, to_block))
+                .await
+                .map_err(|err| anyhow!("Job panicked: {}", panic_message(err)))??;
+            return Ok((rows, Vec::new()));
         }
+        let (a_writer, worker) = self
+            .result_storage
+            .open_writer(job.job_id.clone(), result_file, options)
+            .await;
+        let scanned = spawn_blocking(move || engine.execute_logs(&job, a_writer, from_block, to_block))
+            .await
+            .map_err(|err| anyhow!("Job panicked: {}", panic_message(err)))?;
+        // wait writer, result file published only on success
+        let write_result = worker.await?;
+        let scanned = scanned?;
+        write_result?;
+        Ok(scanned)
     }
 
-    pub async fn execute_job(
+    // Run logs job over block range in own read txn, blocking. Matched logs sent to async writer.
+    // Return logs count and, for follow job, canonical hashes of last blocks of range
+    fn execute_logs(
         &self,
-        job_id: String,
+        job: &JobInfo,
+        a_writer: Sender<Option<LogChunk>>,
         from_block: u64,
         to_block: u64,
-        contract_address: Option<Address>,
-    ) -> Result<()> {
-        let _storage = self.result_storage.clone();
-        let _db_path = self.db_path.clone();
-
-        let flags = EnvironmentFlags {
-            mode: Mode::ReadOnly,
-            exclusive: false,
-            accede: true,
-            no_rdahead: true,
-            no_meminit: true,
-            ..Default::default()
+    ) -> Result<(u64, Vec<(u64, H256)>)> {
+        let (job_id, spec) = (job.job_id.as_str(), &job.spec);
+        let db_env = self.db_env()?;
+        let txn = db_env.begin_ro_txn()?;
+        let _txn_metric = self.metrics.read_txn_opened();
+        let Some(to_block) = checked_range_end(&txn, job, from_block, to_block)? else {
+            // terminate async writer, nothing to export
+            if let Err(err) = a_writer.blocking_send(None) {
+                error!("Send message err:{}", err.to_string())
+            }
+            return Ok((0, Vec::new()));
         };
-        let db_env: Environment<NoWriteMap> = Environment::new()
-            .set_flags(flags)
-            .set_max_dbs(4)
-            .open(_db_path.as_path())
-            .unwrap();
-
-        let open_result = db_env.begin_ro_txn();
-        if open_result.is_err() {
-            panic!("Invalid db {}", open_result.err().unwrap().to_string());
-        }
+        // same snapshot as exported logs
+        let block_hashes = if spec.follow {
+            let window_start = to_block.saturating_sub(REORG_WINDOW - 1).max(from_block);
+            db::chain::canonical_hashes(&txn, window_start, to_block)?
+        } else {
+            Vec::new()
+        };
+        info!(
+            "[{}] Start {:?} job {}-{} {:?}",
+            job_id, spec.kind, from_block, to_block, spec.filter
+        );
+        let progress = Progress::new(from_block, to_block);
+        let scan = LogScan::new(self, job_id, &txn, &spec.filter, &progress)?;
+        let rows = self.export_logs(job_id, a_writer, scan, &progress)?;
+        info!(
+            "[{}] End job. Took:{}mils. Rows:{}",
+            job_id,
+            progress.started().elapsed().as_millis(),
+            rows
+        );
+        Ok((rows, block_hashes))
+    }
 
-        let txn = open_result.unwrap();
-        let db = txn.open_db(Some("TransactionLog")).unwrap();
-        let cursor = txn.cursor(&db).unwrap();
-        let mut key = [0; 8];
-        let mut log_count: u64 = 0;
-        // set from-block number
-        BigEndian::write_u64(&mut key, from_block);
+    // Run table job kind over block range in own read txn, blocking. Return rows count
+    fn execute_table(
+        &self,
+        job: &JobInfo,
+        result_file: &Path,
+        options: &WriterOptions,
+        from_block: u64,
+        to_block: u64,
+    ) -> Result<u64> {
+        let (job_id, spec) = (job.job_id.as_str(), &job.spec);
+        let db_env = self.db_env()?;
+        let txn = db_env.begin_ro_txn()?;
+        let _txn_metric = self.metrics.read_txn_opened();
+        checked_range_end(&txn, job, from_block, to_block)?;
         info!(
-            "[{}] Start job {}-{} {:?}",
-            job_id, from_block, to_block, contract_address
+            "[{}] Start {:?} job {}-{} {:?}",
+            job_id, spec.kind, from_block, to_block, spec.filter
         );
+        let progress = Progress::new(from_block, to_block);
+        let scan = || LogScan::new(self, job_id, &txn, &spec.filter, &progress);
+        let range = (from_block, to_block);
+        let rows = match spec.kind {
+            JobKind::Logs => bail!("Logs job is written by async writer"),
+            JobKind::EventDiscovery => self.discover_events(job_id, result_file, options, scan()?, &progress)?,
+            JobKind::Aggregate => self.aggregate_logs(job_id, result_file, spec, options, scan()?, &progress)?,
+            JobKind::TokenTransfers => self.export_transfers(job_id, result_file, options, scan()?, &progress)?,
+            JobKind::Receipts => self.export_receipts(job_id, result_file, options, &txn, range, &progress)?,
+            JobKind::Transactions => self.export_transactions(job_id, result_file, options, &txn, range, &progress)?,
+            JobKind::Blocks => self.export_blocks(job_id, result_file, options, &txn, range, &progress)?,
+            JobKind::Withdrawals => self.export_withdrawals(job_id, result_file, options, &txn, range, &progress)?,
+            JobKind::StateDiffs => self.export_state_diffs(job_id, result_file, options, &txn, range, &progress)?,
+        };
+        info!(
+            "[{}] End job. Took:{}mils. Rows:{}",
+            job_id,
+            progress.started().elapsed().as_millis(),
+            rows
+        );
+        Ok(rows)
+    }
 
-        let (a_writer, worker) = _storage.open_writer(job_id.clone()).await;
-        let now = Instant::now();
+    // Send matched logs to async writer in chunks, return logs count
+    fn export_logs(
+        &self,
+        job_id: &str,
+        a_writer: Sender<Option<LogChunk>>,
+        scan: LogScan<'_>,
+        progress: &Progress,
+    ) -> Result<u64> {
+        let mut log_count: u64 = 0;
         let mut chunk = Vec::with_capacity(LOG_CHUNK_SIZE);
-        for item in cursor.into_iter_from::<Vec<u8>, Vec<u8>>(&key) {
-            //if item.is_err() {
-            //    panic!("Critical err:{}", item.err().unwrap().to_string());
-            //}
-            let item = item.unwrap();
-            let val = item.1;
-            if val.len() > 0 {
-                let block_n = BigEndian::read_u64(&item.0[0..8]);
-                if block_n > to_block {
+        for tx in scan {
+            let tx = tx?;
+            progress.update(self, job_id, tx.block_n, log_count);
+            for log in tx.logs {
+                chunk.push((tx.block_n, tx.tx_index, log));
+                log_count += 1;
+            }
+            if chunk.len() > LOG_CHUNK_SIZE {
+                info!("[{}] block:{} logs count:{}", job_id, tx.block_n, log_count);
+                let chunk_rows = chunk.len() as u64;
+                // send chunk to async file writer
+                if a_writer.blocking_send(Some(LogChunk { txs: chunk })).is_err() {
+                    // writer failed, error returned by worker
+                    chunk = Vec::new();
                     break;
                 }
-                let tx_index = BigEndian::read_u32(&item.0[8..]);
-                let mut logs: Vec<KvLog> = serde_cbor::from_slice(val.as_slice()).unwrap();
-                // filter by contract address
-                if contract_address.is_some() {
-                    let filter_addr = *contract_address.as_ref().unwrap();
-                    logs.retain(|l| l.address == filter_addr)
-                }
-                if logs.is_empty() {
-                    continue;
-                }
-                for log in logs {
-                    chunk.push((block_n, tx_index, log));
-                    log_count += 1;
-                }
-                if chunk.len() > LOG_CHUNK_SIZE {
-                    info!("[{}] block:{} logs count:{}", job_id, block_n, log_count);
-                    // send chunk to async file writer
-                    a_writer.send(Some(LogChunk { txs: chunk })).await.unwrap();
-                    // allocate new chunk
-                    chunk = Vec::with_capacity(LOG_CHUNK_SIZE);
-                }
+                self.events.publish(
+                    job_id,
+                    JobEvent::ChunkFlushed {
+                        block: tx.block_n,
+                        chunk_rows,
+                        rows: log_count,
+                    },
+                );
+                // allocate new chunk
+                chunk = Vec::with_capacity(LOG_CHUNK_SIZE);
             }
         }
         if !chunk.is_empty() {
             // send buffered records
-            a_writer.send(Some(LogChunk { txs: chunk })).await.unwrap();
+            if let Err(err) = a_writer.blocking_send(Some(LogChunk { txs: chunk })) {
+                error!("Send message err:{}", err.to_string())
+            }
         }
 
         // terminate async writer
-        if let Err(err) = a_writer.send(None).await {
+        if let Err(err) = a_writer.blocking_send(None) {
             error!("Send message err:{}", err.to_string())
         }
-        // wait writer
-        let _ = join!(worker);
+        Ok(log_count)
+    }
+}
 
-        info!(
-            "[{}] End job. Took:{}mils. Logs count:{}",
-            job_id,
-            now.elapsed().as_millis(),
-            log_count
+// Last block of range executed and canonical in txn snapshot. Blocks unwound after job submit or poll
+// fail job, follow job stops short and exports them on next poll. None if no block of range is there
+fn checked_range_end(
+    txn: &Transaction<RO, NoWriteMap>,
+    job: &JobInfo,
+    from_block: u64,
+    to_block: u64,
+) -> Result<Option<u64>> {
+    let head = db::chain::canonical_head(txn, from_block, to_block)?;
+    if head != Some(to_block) {
+        if !job.spec.follow {
+            bail!("Blocks {}-{} not executed or not canonical, last block:{:?}", from_block, to_block, head);
+        }
+        warn!(
+            "[{}] Blocks up to {} not executed or not canonical yet, last block:{:?}",
+            job.job_id, to_block, head
         );
+    }
+    Ok(head)
+}
 
-        Ok(())
+// Payload of panicked job task
+fn panic_message(err: JoinError) -> String {
+    match err.try_into_panic() {
+        Ok(panic) => panic
+            .downcast_ref::<&str>()
+            .map(|msg| msg.to_string())
+            .or_else(|| panic.downcast_ref::<String>().cloned())
+            .unwrap_or_default(),
+        Err(err) => err.to_string(),
     }
 }
 
 #[cfg(test)]
 mod test {
+    use crate::db::block::test::header_rlp;
+    use crate::db::block::{block_key, Withdrawal, DYNAMIC_FEE_TX_TYPE};
+    use crate::db::chain::{STAGE_ACCOUNT_HISTORY_INDEX, STAGE_EXECUTION, STAGE_STORAGE_HISTORY_INDEX};
+    use crate::db::chain::{FINALIZED_BLOCK_KEY, SAFE_BLOCK_KEY};
+    use crate::db::{
+        ACCOUNT_CHANGESET_TABLE, ACCOUNT_HISTORY_TABLE, BLOCK_BODY_TABLE, CANONICAL_HEADER_TABLE, ETH_TX_TABLE,
+        HEADERS_TABLE, HEADER_NUMBER_TABLE, LAST_FORKCHOICE_TABLE, LOGS_TABLE, PLAIN_STATE_TABLE, RECEIPTS_TABLE,
+        SENDERS_TABLE, STORAGE_CHANGESET_TABLE, SYNC_STAGE_TABLE,
+    };
     use crate::engine::SearchEngine;
+    use crate::job::events::JobEvent;
+    use crate::job::spec::{AggregateSpec, BlockTag, Finality, GroupBy, JobKind, JobSpec};
+    use crate::job::store::JobStore;
+    use crate::job::{JobInfo, JobRegistry, JobStatus, RegisterError, JOB_STORE_DIR};
+    use crate::metrics::Metrics;
     use crate::storage::ResultStorage;
     use crate::util::setup_log;
-    use std::path::PathBuf;
-    use std::sync::Arc;
+    use crate::webhook::WebhookNotifier;
+    use ethers::types::{Address, H256, U256};
+    use ethers::utils::get_contract_address;
+    use ethers::utils::rlp::{Encodable, RlpStream};
+    use libmdbx::{DatabaseFlags, Environment, NoWriteMap, Transaction, WriteFlags, RW};
+    use parquet::file::reader::{FileReader, SerializedFileReader};
+    use parquet::record::{Row, RowAccessor};
+    use roaring::RoaringTreemap;
+    use serde_cbor::Value;
+    use std::fs;
+    use std::path::{Path, PathBuf};
+    use std::sync::{Arc, Mutex, Weak};
+    use std::time::Duration;
+    use tokio::time::sleep;
+
+    // Job id of job panicking in dispatcher
+    pub(super) const PANIC_JOB_ID: &str = "panic_hook";
+
+    // Env of fake chaindata, libmdbx allows one env per db in process: shared by test writer and engine
+    fn test_env(db_path: &Path) -> Arc<Environment<NoWriteMap>> {
+        static ENVS: Mutex<Vec<(PathBuf, Weak<Environment<NoWriteMap>>)>> = Mutex::new(Vec::new());
+        let mut envs = ENVS.lock().unwrap();
+        envs.retain(|(_, env)| env.strong_count() > 0);
+        if let Some(env) = envs.iter().find(|(path, _)| path == db_path).and_then(|(_, env)| env.upgrade()) {
+            return env;
+        }
+        let env = Arc::new(Environment::new().set_max_dbs(32).open(db_path).unwrap());
+        envs.push((db_path.to_path_buf(), Arc::downgrade(&env)));
+        env
+    }
+
+    pub(super) fn new_engine(db_path: &Path, result_path: &Path) -> (Arc<ResultStorage>, Arc<JobRegistry>, Arc<SearchEngine>) {
+        let metrics = Arc::new(Metrics::new());
+        let result_storage = Arc::new(ResultStorage::new(result_path.to_path_buf(), metrics.clone()));
+        let job_store = JobStore::open(&result_path.join(JOB_STORE_DIR)).unwrap();
+        let job_registry = Arc::new(JobRegistry::open(result_storage.clone(), job_store).unwrap());
+        let engine = Arc::new(SearchEngine::new(
+            db_path.to_path_buf(),
+            result_storage.clone(),
+            job_registry.clone(),
+            1,
+            Arc::new(WebhookNotifier::new(None, 0, Duration::from_secs(10))),
+            metrics,
+            Duration::from_millis(50),
+        ));
+        if db_path.exists() {
+            *engine.db_env.lock().unwrap() = Some(test_env(db_path));
+        }
+        (result_storage, job_registry, engine)
+    }
 
     #[tokio::test]
     async fn execute_job() {
         setup_log();
-        let result_path = "/tmp";
-        let db_path = "/home/art/dev/sepolia-chaindata/";
-        let result_storage = Arc::new(ResultStorage::new(PathBuf::from(result_path)));
-        let engine = Arc::new(SearchEngine::new(PathBuf::from(db_path), result_storage.clone()));
+        let dir = tempfile::tempdir().unwrap();
+        let db_path = dir.path().join("chaindata");
+        write_db(&db_path, 30, &[(5, 0), (10, 0), (10, 1), (30, 0)], None);
+        let (result_storage, job_registry, engine) = new_engine(&db_path, dir.path());
 
-        engine
-            .execute_job("test-1".to_string(), 10_000, 3_000_000, None)
-            .await
+        let spec = JobSpec {
+            job_id: Some("test-1".to_string()),
+            from_block: 10,
+            to_block: BlockTag::Number(30),
+            ..Default::default()
+        };
+        let job = job_registry.register(spec, 30, Finality::Unfinalized).unwrap();
+        assert_eq!(engine.execute_job(&job).await.unwrap(), 3);
+        assert!(result_storage.result_file("test-1").exists());
+    }
+
+    // Canonical hash of block, blocks from fork_block on another branch
+    fn block_hash(block_n: u64, fork_block: Option<u64>) -> H256 {
+        match fork_block {
+            Some(fork_block) if block_n >= fork_block => H256::from_low_u64_be(block_n + 1_000),
+            _ => H256::from_low_u64_be(block_n),
+        }
+    }
+
+    // Fake chaindata: execution stage progress, canonical hashes up to executed block
+    // and one log per (block, tx). Replaces previous hashes and logs
+    pub(super) fn write_db(db_path: &Path, executed: u64, logs: &[(u64, u32)], fork_block: Option<u64>) {
+        fs::create_dir_all(db_path).unwrap();
+        write_tables(db_path, |txn| {
+            let db = txn.create_db(Some(SYNC_STAGE_TABLE), DatabaseFlags::default()).unwrap();
+            txn.put(&db, STAGE_EXECUTION.as_bytes(), executed.to_be_bytes(), WriteFlags::UPSERT)
+                .unwrap();
+            let db = txn.create_db(Some(CANONICAL_HEADER_TABLE), DatabaseFlags::default()).unwrap();
+            txn.clear_db(&db).unwrap();
+            for block_n in 0..=executed {
+                txn.put(&db, block_n.to_be_bytes(), block_hash(block_n, fork_block), WriteFlags::UPSERT)
+                    .unwrap();
+            }
+            let db = txn.create_db(Some(LOGS_TABLE), DatabaseFlags::default()).unwrap();
+            txn.clear_db(&db).unwrap();
+            for (block_n, tx_index) in logs {
+                let mut key = block_n.to_be_bytes().to_vec();
+                key.extend_from_slice(&tx_index.to_be_bytes());
+                // Erigon CBOR log: [address, [topics], data]
+                let log = Value::Array(vec![
+                    Value::Bytes(vec![1; 20]),
+                    Value::Array(vec![Value::Bytes(vec![2; 32])]),
+                    Value::Bytes(vec![3; 4]),
+                ]);
+                let value = serde_cbor::to_vec(&Value::Array(vec![log])).unwrap();
+                txn.put(&db, key, value, WriteFlags::UPSERT).unwrap();
+            }
+        });
+    }
+
+    // Write to fake chaindata in one txn
+    fn write_tables(db_path: &Path, write: impl FnOnce(&Transaction<RW, NoWriteMap>)) {
+        let env = test_env(db_path);
+        let txn = env.begin_rw_txn().unwrap();
+        write(&txn);
+        txn.commit().unwrap();
+    }
+
+    // Fork choice of consensus layer, hashes of canonical blocks
+    fn write_forkchoice(db_path: &Path, safe: u64, finalized: u64) {
+        write_tables(db_path, |txn| {
+            let forkchoice = txn.create_db(Some(LAST_FORKCHOICE_TABLE), DatabaseFlags::default()).unwrap();
+            let header_number = txn.create_db(Some(HEADER_NUMBER_TABLE), DatabaseFlags::default()).unwrap();
+            for (key, block_n) in [(SAFE_BLOCK_KEY, safe), (FINALIZED_BLOCK_KEY, finalized)] {
+                let hash = block_hash(block_n, None);
+                txn.put(&forkchoice, key, hash, WriteFlags::UPSERT).unwrap();
+                txn.put(&header_number, hash, block_n.to_be_bytes(), WriteFlags::UPSERT)
+                    .unwrap();
+            }
+        });
+    }
+
+    #[tokio::test]
+    async fn panicked_job_fails() {
+        let dir = tempfile::tempdir().unwrap();
+        let db_path = dir.path().join("chaindata");
+        write_db(&db_path, 10, &[], None);
+        let (_, job_registry, engine) = new_engine(&db_path, dir.path());
+        engine.start_dispatcher();
+
+        let job_id = engine
+            .submit_job(JobSpec {
+                job_id: Some(PANIC_JOB_ID.to_string()),
+                to_block: BlockTag::Latest,
+                ..Default::default()
+            })
             .unwrap();
+        let job = wait_for(&job_registry, &job_id, |job| matches!(job.status, JobStatus::Failed(_))).await;
+        assert!(matches!(job.status, JobStatus::Failed(err) if err.starts_with("Job panicked")));
+    }
+
+    #[tokio::test]
+    async fn malformed_log_key_fails() {
+        let dir = tempfile::tempdir().unwrap();
+        let db_path = dir.path().join("chaindata");
+        write_db(&db_path, 10, &[], None);
+        write_tables(&db_path, |txn| {
+            // key without tx index
+            let db = txn.open_db(Some(LOGS_TABLE)).unwrap();
+            txn.put(&db, 5u64.to_be_bytes(), [0x80], WriteFlags::UPSERT).unwrap();
+        });
+        let (_, job_registry, engine) = new_engine(&db_path, dir.path());
+        engine.start_dispatcher();
+
+        let job_id = engine
+            .submit_job(JobSpec {
+                to_block: BlockTag::Latest,
+                ..Default::default()
+            })
+            .unwrap();
+        let job = wait_for(&job_registry, &job_id, |job| matches!(job.status, JobStatus::Failed(_))).await;
+        assert_eq!(job.status, JobStatus::Failed("Malformed log key".to_string()));
+    }
+
+    #[test]
+    fn submit_job_range() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path();
+        let db_path = path.join("chaindata");
+        write_db(&db_path, 100, &[], None);
+        let (_, job_registry, engine) = new_engine(&db_path, path);
+        let spec = |job_id: &str, from_block: u64, to_block: BlockTag| JobSpec {
+            job_id: Some(job_id.to_string()),
+            from_block,
+            to_block,
+            ..Default::default()
+        };
+
+        assert!(matches!(
+            engine.submit_job(spec("beyond", 10, BlockTag::Number(101))),
+            Err(RegisterError::BeyondExecuted {
+                field: "to_block",
+                block: 101,
+                executed: 100
+            })
+        ));
+        assert!(matches!(
+            engine.submit_job(spec("from_beyond", 101, BlockTag::Latest)),
+            Err(RegisterError::BeyondExecuted { field: "from_block", .. })
+        ));
+        engine.submit_job(spec("head", 10, BlockTag::Number(100))).unwrap();
+        engine.submit_job(spec("latest", 10, BlockTag::Latest)).unwrap();
+        let job = job_registry.get("latest").unwrap();
+        assert_eq!((job.spec.to_block, job.to_block), (BlockTag::Latest, 100));
+        assert!(matches!(
+            engine.submit_job(spec("no_forkchoice", 10, BlockTag::Finalized)),
+            Err(RegisterError::NoForkchoice(BlockTag::Finalized))
+        ));
+
+        write_forkchoice(&db_path, 90, 80);
+        engine.submit_job(spec("finalized", 10, BlockTag::Finalized)).unwrap();
+        engine.submit_job(spec("safe", 10, BlockTag::Safe)).unwrap();
+        engine.submit_job(spec("old", 10, BlockTag::Number(50))).unwrap();
+        for (job_id, to_block, finality) in [
+            ("finalized", 80, Finality::Finalized),
+            ("safe", 90, Finality::Safe),
+            ("old", 50, Finality::Finalized),
+            ("latest", 100, Finality::Unfinalized),
+        ] {
+            let job = job_registry.get(job_id).unwrap();
+            assert_eq!((job.to_block, job.finality), (to_block, finality));
+        }
+
+        // finality computed at submit, not taken from client
+        let body = r#"{"job_id": "client", "from_block": 10, "to_block": 100, "finality": "finalized"}"#;
+        let errors = JobSpec::from_json(body).unwrap_err();
+        assert!(errors[0].message.contains("unknown field `finality`"));
+        let body = r#"{"job_id": "client", "from_block": 10, "to_block": 100}"#;
+        engine.submit_job(JobSpec::from_json(body).unwrap()).unwrap();
+        assert_eq!(job_registry.get("client").unwrap().finality, Finality::Unfinalized);
+    }
+
+    async fn wait_for(job_registry: &JobRegistry, job_id: &str, f: impl Fn(&JobInfo) -> bool) -> JobInfo {
+        for _ in 0..100 {
+            let job = job_registry.get(job_id).unwrap();
+            if f(&job) {
+                return job;
+            }
+            sleep(Duration::from_millis(50)).await;
+        }
+        panic!("Job {} state not reached", job_id);
+    }
+
+    // Run job on chaindata written by setup in temp dir, return completed job and rows of result file
+    async fn run_job(spec: JobSpec, setup: impl FnOnce(&Path)) -> (JobInfo, Vec<Row>) {
+        let dir = tempfile::tempdir().unwrap();
+        let db_path = dir.path().join("chaindata");
+        setup(&db_path);
+        let (result_storage, job_registry, engine) = new_engine(&db_path, dir.path());
+        engine.start_dispatcher();
+
+        let job_id = engine.submit_job(spec).unwrap();
+        let job = wait_for(&job_registry, &job_id, |job| {
+            matches!(job.status, JobStatus::Completed | JobStatus::Failed(_))
+        })
+        .await;
+        assert_eq!(job.status, JobStatus::Completed);
+        let reader = SerializedFileReader::new(fs::File::open(result_storage.result_file(&job_id)).unwrap()).unwrap();
+        let rows = reader.get_row_iter(None).unwrap().map(|row| row.unwrap()).collect();
+        (job, rows)
+    }
+
+    #[tokio::test]
+    async fn event_discovery_job() {
+        let spec = JobSpec {
+            to_block: BlockTag::Latest,
+            kind: JobKind::EventDiscovery,
+            ..Default::default()
+        };
+        let logs = [(5, 0), (5, 1), (12, 0), (21, 0)];
+        let (job, rows) = run_job(spec, |db_path| write_db(db_path, 20, &logs, None)).await;
+        assert_eq!(job.rows, Some(1));
+        assert_eq!(rows.len(), 1);
+        let row = &rows[0];
+        assert_eq!(row.get_bytes(0).unwrap().data(), &[1; 20]);
+        assert_eq!(row.get_bytes(1).unwrap().data(), &[2; 32]);
+        assert_eq!(row.get_int(2).unwrap(), 1);
+        // count, first and last block
+        assert_eq!((row.get_long(3).unwrap(), row.get_long(4).unwrap(), row.get_long(5).unwrap()), (3, 5, 12));
+    }
+
+    #[tokio::test]
+    async fn aggregate_job() {
+        let spec = JobSpec {
+            to_block: BlockTag::Latest,
+            kind: JobKind::Aggregate,
+            aggregate: Some(AggregateSpec {
+                group_by: vec![GroupBy::Block, GroupBy::Contract],
+                block_bucket: 10,
+            }),
+            ..Default::default()
+        };
+        let logs = [(5, 0), (5, 1), (12, 0), (14, 0), (20, 0)];
+        let (job, rows) = run_job(spec, |db_path| write_db(db_path, 20, &logs, None)).await;
+        assert_eq!(job.rows, Some(3));
+        let rows: Vec<(Vec<u8>, i64, i64, i64)> = rows
+            .iter()
+            .map(|row| {
+                let contract = row.get_bytes(0).unwrap().data().to_vec();
+                (contract, row.get_long(1).unwrap(), row.get_long(2).unwrap(), row.get_long(3).unwrap())
+            })
+            .collect();
+        // contract, bucket start block, count, data bytes
+        assert_eq!(
+            rows,
+            vec![(vec![1; 20], 0, 2, 8), (vec![1; 20], 10, 2, 8), (vec![1; 20], 20, 1, 4)]
+        );
+    }
+
+    // type, post_state, status, cumulative_gas_used
+    type Receipt = (u8, Vec<u8>, u64, u64);
+
+    // Erigon CBOR receipts by block
+    fn write_receipts(db_path: &Path, receipts: &[(u64, Vec<Receipt>)]) {
+        write_tables(db_path, |txn| {
+            let db = txn.create_db(Some(RECEIPTS_TABLE), DatabaseFlags::default()).unwrap();
+            for (block_n, block_receipts) in receipts {
+                let value = Value::Array(
+                    block_receipts
+                        .iter()
+                        .map(|(tx_type, post_state, status, cumulative_gas)| {
+                            Value::Array(vec![
+                                Value::Integer(*tx_type as i128),
+                                Value::Bytes(post_state.clone()),
+                                Value::Integer(*status as i128),
+                                Value::Integer(*cumulative_gas as i128),
+                            ])
+                        })
+                        .collect(),
+                );
+                txn.put(&db, block_n.to_be_bytes(), serde_cbor::to_vec(&value).unwrap(), WriteFlags::UPSERT)
+                    .unwrap();
+            }
+        });
+    }
+
+    #[tokio::test]
+    async fn receipts_job() {
+        let sender = Address::from_low_u64_be(1);
+        let legacy = |nonce: u64, to: Option<Address>| {
+            let mut tx = RlpStream::new_list(9);
+            tx.append(&nonce).append(&U256::from(10)).append(&21_000_u64);
+            match to {
+                Some(to) => tx.append(&to),
+                None => tx.append(&""),
+            };
+            tx.append(&U256::zero()).append(&"").append(&27_u64).append(&U256::one()).append(&U256::one());
+            tx.out().to_vec()
+        };
+        let spec = JobSpec {
+            to_block: BlockTag::Latest,
+            kind: JobKind::Receipts,
+            ..Default::default()
+        };
+        let (job, rows) = run_job(spec, |db_path| {
+            write_db(db_path, 12, &[(5, 1), (12, 0), (13, 0)], None);
+            write_receipts(
+                db_path,
+                &[
+                    (5, vec![(0, vec![], 1, 21_000), (2, vec![], 0, 50_000)]),
+                    (8, vec![(0, vec![], 1, 21_000)]),
+                    (10, vec![(0, vec![], 1, 53_000)]),
+                    (12, vec![(0, vec![9; 32], 0, 30_000)]),
+                    (13, vec![(2, vec![], 1, 21_000)]),
+                ],
+            );
+            write_body(db_path, 5, 0, &[(sender, legacy(1, Some(sender))), (sender, legacy(2, None))], &[]);
+            write_body(db_path, 12, 4, &[(sender, legacy(3, Some(sender)))], &[]);
+            // no body of block 8, no senders of block 10
+            write_body(db_path, 10, 7, &[(sender, legacy(4, None))], &[]);
+            write_tables(db_path, |txn| {
+                let db = txn.open_db(Some(SENDERS_TABLE)).unwrap();
+                txn.del(&db, block_key(10, &block_hash(10, None)), None).unwrap();
+            });
+        })
+        .await;
+        assert_eq!(job.rows, Some(5));
+        // block_n, tx_n, tx_type, status, cumulative gas, gas used, log count
+        let values: Vec<_> = rows
+            .iter()
+            .map(|row| {
+                (
+                    row.get_long(0).unwrap(),
+                    row.get_int(1).unwrap(),
+                    row.get_int(2).unwrap(),
+                    row.get_int(3).ok(),
+                    row.get_long(5).unwrap(),
+                    row.get_long(6).unwrap(),
+                    row.get_int(7).unwrap(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            values,
+            vec![
+                (5, 0, 0, Some(1), 21_000, 21_000, 0),
+                (5, 1, 2, Some(0), 50_000, 29_000, 1),
+                (8, 0, 0, Some(1), 21_000, 21_000, 0),
+                (10, 0, 0, Some(1), 53_000, 53_000, 0),
+                (12, 0, 0, None, 30_000, 30_000, 1)
+            ]
+        );
+        // pre-Byzantium state root
+        assert_eq!(rows[4].get_bytes(4).unwrap().data(), &[9; 32]);
+        assert!(rows[0].get_bytes(4).is_err());
+        // contract creation tx
+        assert_eq!(rows[1].get_bytes(8).unwrap().data(), get_contract_address(sender, 2).as_bytes());
+        assert!(rows[0].get_bytes(8).is_err());
+        // body or sender missing
+        assert!(rows[2].get_bytes(8).is_err());
+        assert!(rows[3].get_bytes(8).is_err());
+    }
+
+    // Body with system txs around block txs, tx ids from base_tx_id
+    fn write_body(
+        db_path: &Path,
+        block_n: u64,
+        base_tx_id: u64,
+        txs: &[(Address, Vec<u8>)],
+        withdrawals: &[Withdrawal],
+    ) {
+        let key = block_key(block_n, &block_hash(block_n, None));
+        let mut body = RlpStream::new_list(if withdrawals.is_empty() { 3 } else { 4 });
+        body.append(&base_tx_id).append(&(txs.len() as u32 + 2)).begin_list(0);
+        if !withdrawals.is_empty() {
+            body.begin_list(withdrawals.len());
+            for w in withdrawals {
+                body.begin_list(4)
+                    .append(&w.index)
+                    .append(&w.validator_index)
+                    .append(&w.address)
+                    .append(&w.amount_gwei);
+            }
+        }
+        write_tables(db_path, |txn| {
+            let db = txn.create_db(Some(BLOCK_BODY_TABLE), DatabaseFlags::default()).unwrap();
+            txn.put(&db, &key, body.out(), WriteFlags::UPSERT).unwrap();
+            let db = txn.create_db(Some(ETH_TX_TABLE), DatabaseFlags::default()).unwrap();
+            for (i, (_, tx)) in txs.iter().enumerate() {
+                txn.put(&db, (base_tx_id + 1 + i as u64).to_be_bytes(), tx, WriteFlags::UPSERT)
+                    .unwrap();
+            }
+            let senders: Vec<u8> = txs.iter().flat_map(|(sender, _)| sender.as_bytes().to_vec()).collect();
+            let db = txn.create_db(Some(SENDERS_TABLE), DatabaseFlags::default()).unwrap();
+            txn.put(&db, &key, senders, WriteFlags::UPSERT).unwrap();
+        });
+    }
+
+    #[tokio::test]
+    async fn transactions_job() {
+        let sender = Address::from_low_u64_be(1);
+        let to = Address::from_low_u64_be(2);
+        let mut legacy = RlpStream::new_list(9);
+        legacy.append(&3_u64).append(&U256::from(10)).append(&21_000_u64).append(&to);
+        legacy.append(&U256::from(1_000)).append(&"").append(&37_u64).append(&U256::one()).append(&U256::one());
+        let mut dynamic_fee = RlpStream::new_list(12);
+        dynamic_fee.append(&1_u64).append(&4_u64).append(&U256::from(1)).append(&U256::from(20));
+        dynamic_fee.append(&60_000_u64).append(&"").append(&U256::zero()).append(&vec![0x60_u8, 0x80]);
+        dynamic_fee.begin_list(0).append(&0_u64).append(&U256::one()).append(&U256::one());
+        let mut dynamic_fee = dynamic_fee.out().to_vec();
+        dynamic_fee.insert(0, DYNAMIC_FEE_TX_TYPE);
+        let spec = JobSpec {
+            from_block: 5,
+            to_block: BlockTag::Number(7),
+            kind: JobKind::Transactions,
+            ..Default::default()
+        };
+        let (job, rows) = run_job(spec, |db_path| {
+            write_db(db_path, 10, &[], None);
+            write_body(db_path, 5, 10, &[(sender, legacy.out().to_vec())], &[]);
+            write_body(db_path, 6, 13, &[], &[]);
+            write_body(db_path, 7, 15, &[(sender, dynamic_fee)], &[]);
+        })
+        .await;
+        assert_eq!(job.rows, Some(2));
+        // block_n, tx_type, chain_id, nonce, gas
+        let values: Vec<_> = rows
+            .iter()
+            .map(|row| {
+                (
+                    row.get_long(0).unwrap(),
+                    row.get_int(3).unwrap(),
+                    row.get_long(4).unwrap(),
+                    row.get_long(5).unwrap(),
+                    row.get_long(9).unwrap(),
+                )
+            })
+            .collect();
+        assert_eq!(values, vec![(5, 0, 1, 3, 21_000), (7, 2, 1, 4, 60_000)]);
+        assert_eq!(rows[0].get_bytes(6).unwrap().data(), sender.as_bytes());
+        assert_eq!(rows[0].get_bytes(7).unwrap().data(), to.as_bytes());
+        assert_eq!(rows[0].get_bytes(10).unwrap().data(), H256::from_low_u64_be(10).as_bytes());
+        // fields not in envelope
+        assert!(rows[1].get_bytes(7).is_err());
+        assert!(rows[1].get_bytes(10).is_err());
+        assert_eq!(rows[1].get_bytes(12).unwrap().data(), H256::from_low_u64_be(20).as_bytes());
+        assert_eq!(rows[1].get_bytes(14).unwrap().data(), &[0x60, 0x80]);
+        assert_eq!(rows[1].get_string(15).unwrap(), "[]");
+    }
+
+    #[tokio::test]
+    async fn blocks_job() {
+        let spec = JobSpec {
+            from_block: 2,
+            to_block: BlockTag::Number(3),
+            kind: JobKind::Blocks,
+            ..Default::default()
+        };
+        let (job, rows) = run_job(spec, |db_path| {
+            write_db(db_path, 10, &[], None);
+            write_tables(db_path, |txn| {
+                let db = txn.create_db(Some(HEADERS_TABLE), DatabaseFlags::default()).unwrap();
+                let base_fee = U256::from(7);
+                for block_n in 1..=3 {
+                    let forks: Vec<&dyn Encodable> = if block_n == 3 { vec![&base_fee] } else { vec![] };
+                    let key = block_key(block_n, &block_hash(block_n, None));
+                    txn.put(&db, key, header_rlp(block_n, &forks), WriteFlags::UPSERT).unwrap();
+                }
+            });
+        })
+        .await;
+        assert_eq!(job.rows, Some(2));
+        assert_eq!(rows[0].get_long(0).unwrap(), 2);
+        assert_eq!(rows[0].get_bytes(1).unwrap().data(), block_hash(2, None).as_bytes());
+        assert_eq!(rows[0].get_long(3).unwrap(), 1_700_000_024);
+        assert!(rows[0].get_bytes(8).is_err());
+        assert_eq!(rows[1].get_bytes(8).unwrap().data(), H256::from_low_u64_be(7).as_bytes());
+    }
+
+    #[tokio::test]
+    async fn withdrawals_job() {
+        let withdrawal = |index: u64| Withdrawal {
+            index,
+            validator_index: 1_000 + index,
+            address: Address::from_low_u64_be(index),
+            amount_gwei: 10 * index,
+        };
+        let spec = JobSpec {
+            from_block: 5,
+            to_block: BlockTag::Number(7),
+            kind: JobKind::Withdrawals,
+            ..Default::default()
+        };
+        let (job, rows) = run_job(spec, |db_path| {
+            write_db(db_path, 10, &[], None);
+            write_body(db_path, 5, 0, &[], &[]);
+            write_body(db_path, 6, 2, &[], &[withdrawal(1), withdrawal(2)]);
+            write_body(db_path, 7, 4, &[], &[withdrawal(3)]);
+        })
+        .await;
+        assert_eq!(job.rows, Some(3));
+        let rows: Vec<_> = rows
+            .iter()
+            .map(|row| {
+                (
+                    row.get_long(0).unwrap(),
+                    row.get_long(1).unwrap(),
+                    row.get_long(2).unwrap(),
+                    row.get_bytes(3).unwrap().data().to_vec(),
+                    row.get_long(4).unwrap(),
+                )
+            })
+            .collect();
+        assert_eq!(rows[0], (6, 1, 1_001, Address::from_low_u64_be(1).as_bytes().to_vec(), 10));
+        assert_eq!(rows[2], (7, 3, 1_003, Address::from_low_u64_be(3).as_bytes().to_vec(), 30));
+    }
+
+    // Account changes, then storage changes of contract incarnation 1: (block, key, previous value)
+    fn write_change_sets(
+        txn: &Transaction<RW, NoWriteMap>,
+        accounts: &[(u64, Address, Vec<u8>)],
+        storage: &[(u64, Address, H256, Vec<u8>)],
+    ) {
+        let db = txn.create_db(Some(ACCOUNT_CHANGESET_TABLE), DatabaseFlags::DUP_SORT).unwrap();
+        for (block_n, address, prev) in accounts {
+            txn.put(&db, block_n.to_be_bytes(), [address.as_bytes(), prev].concat(), WriteFlags::UPSERT)
+                .unwrap();
+        }
+        let db = txn.create_db(Some(STORAGE_CHANGESET_TABLE), DatabaseFlags::DUP_SORT).unwrap();
+        for (block_n, address, slot, prev) in storage {
+            let key = [&block_n.to_be_bytes(), address.as_bytes(), &1u64.to_be_bytes()].concat();
+            txn.put(&db, key, [slot.as_bytes(), prev].concat(), WriteFlags::UPSERT).unwrap();
+        }
+    }
+
+    #[tokio::test]
+    async fn state_diffs_job() {
+        let (a, b) = (Address::from_low_u64_be(0xa), Address::from_low_u64_be(0xb));
+        // nonce and balance set
+        let account = |nonce: u8, balance: u8| vec![0b11, 1, nonce, 1, balance];
+        let slot = H256::from_low_u64_be;
+        let spec = JobSpec {
+            from_block: 5,
+            to_block: BlockTag::Number(6),
+            kind: JobKind::StateDiffs,
+            ..Default::default()
+        };
+        let (job, rows) = run_job(spec, |db_path| {
+            write_db(db_path, 10, &[], None);
+            write_tables(db_path, |txn| {
+                // a created in block 5
+                let accounts = [(5, a, vec![]), (6, a, account(1, 100)), (6, b, account(1, 7)), (8, a, account(2, 50))];
+                let storage = [
+                    (5, a, slot(1), vec![]),
+                    (6, a, slot(2), vec![9]),
+                    (6, a, slot(3), vec![]),
+                    (7, a, slot(1), vec![5]),
+                ];
+                write_change_sets(txn, &accounts, &storage);
+                let db = txn.create_db(Some(PLAIN_STATE_TABLE), DatabaseFlags::DUP_SORT).unwrap();
+                txn.put(&db, a, account(3, 40), WriteFlags::UPSERT).unwrap();
+                txn.put(&db, b, account(2, 3), WriteFlags::UPSERT).unwrap();
+                // slot 2 cleared
+                let key = [a.as_bytes(), &1u64.to_be_bytes()].concat();
+                for (slot, value) in [(slot(1), vec![7]), (slot(3), vec![0xb])] {
+                    txn.put(&db, &key, [slot.as_bytes(), &value].concat(), WriteFlags::UPSERT).unwrap();
+                }
+            });
+        })
+        .await;
+        assert_eq!(job.rows, Some(6));
+        let bytes = |row: &Row, i: usize| row.get_bytes(i).ok().map(|b| b.data().to_vec());
+        let word = |n: u64| Some(H256::from_low_u64_be(n).as_bytes().to_vec());
+        // block_n, address, slot, prev value, new value
+        let storage: Vec<_> = rows
+            .iter()
+            .map(|row| {
+                (
+                    row.get_long(0).unwrap(),
+                    Address::from_slice(row.get_bytes(1).unwrap().data()),
+                    bytes(row, 2),
+                    bytes(row, 3),
+                    bytes(row, 4),
+                )
+            })
+            .collect();
+        assert_eq!(
+            storage,
+            vec![
+                (5, a, None, None, None),
+                (5, a, word(1), word(0), word(5)),
+                (6, a, None, None, None),
+                (6, b, None, None, None),
+                (6, a, word(2), word(9), word(0)),
+                (6, a, word(3), word(0), word(0xb)),
+            ]
+        );
+        // prev nonce, new nonce, prev balance, new balance of account rows, balance as 32 bytes big-endian
+        let accounts: Vec<_> = [0, 2, 3]
+            .iter()
+            .map(|i| {
+                let row = &rows[*i];
+                (row.get_long(5).ok(), row.get_long(6).ok(), bytes(row, 7), bytes(row, 8))
+            })
+            .collect();
+        assert_eq!(
+            accounts,
+            vec![
+                (None, Some(1), None, word(100)),
+                (Some(1), Some(2), word(100), word(50)),
+                (Some(1), Some(2), word(7), word(3)),
+            ]
+        );
+        assert!(rows[1].get_long(5).is_err());
+    }
+
+    #[tokio::test]
+    async fn state_diffs_history_index() {
+        let [a, b, c, d] = [0xa, 0xb, 0xc, 0xd].map(Address::from_low_u64_be);
+        let account = |nonce: u8| vec![0b1, 1, nonce];
+        let spec = JobSpec {
+            from_block: 5,
+            to_block: BlockTag::Number(6),
+            kind: JobKind::StateDiffs,
+            ..Default::default()
+        };
+        let (job, rows) = run_job(spec, |db_path| {
+            write_db(db_path, 10, &[], None);
+            write_tables(db_path, |txn| {
+                // history indexed up to block 9
+                let db = txn.open_db(Some(SYNC_STAGE_TABLE)).unwrap();
+                for stage in [STAGE_ACCOUNT_HISTORY_INDEX, STAGE_STORAGE_HISTORY_INDEX] {
+                    txn.put(&db, stage, 9u64.to_be_bytes(), WriteFlags::UPSERT).unwrap();
+                }
+                // b changed in range only, c unrelated after range, d changed after indexed block
+                let changes = [
+                    (5, a, account(1)),
+                    (6, b, account(1)),
+                    (6, d, account(1)),
+                    (8, a, account(2)),
+                    (9, c, account(1)),
+                    (10, d, account(2)),
+                ];
+                write_change_sets(txn, &changes, &[]);
+                let db = txn.create_db(Some(ACCOUNT_HISTORY_TABLE), DatabaseFlags::default()).unwrap();
+                for (address, blocks) in [(a, vec![5, 8]), (b, vec![6]), (c, vec![9]), (d, vec![6])] {
+                    let mut bitmap = Vec::new();
+                    RoaringTreemap::from_iter(blocks).serialize_into(&mut bitmap).unwrap();
+                    let key = [address.as_bytes(), &u64::MAX.to_be_bytes()].concat();
+                    txn.put(&db, key, bitmap, WriteFlags::UPSERT).unwrap();
+                }
+                let db = txn.create_db(Some(PLAIN_STATE_TABLE), DatabaseFlags::DUP_SORT).unwrap();
+                for (address, nonce) in [(a, 3), (b, 4), (c, 2), (d, 3)] {
+                    txn.put(&db, address, account(nonce), WriteFlags::UPSERT).unwrap();
+                }
+            });
+        })
+        .await;
+        assert_eq!(job.rows, Some(3));
+        // block_n, address, prev nonce, new nonce
+        let diffs: Vec<_> = rows
+            .iter()
+            .map(|row| {
+                let address = Address::from_slice(row.get_bytes(1).unwrap().data());
+                (row.get_long(0).unwrap(), address, row.get_long(5).ok(), row.get_long(6).ok())
+            })
+            .collect();
+        // new value by history index, PlainState, change set of block not indexed
+        assert_eq!(
+            diffs,
+            vec![(5, a, Some(1), Some(2)), (6, b, Some(1), Some(4)), (6, d, Some(1), Some(2))]
+        );
+    }
+
+    #[tokio::test]
+    async fn state_diffs_index_lag() {
+        let dir = tempfile::tempdir().unwrap();
+        let db_path = dir.path().join("chaindata");
+        // history not indexed, execution far ahead
+        write_db(&db_path, 1_100, &[], None);
+        let (_, job_registry, engine) = new_engine(&db_path, dir.path());
+        engine.start_dispatcher();
+
+        let job_id = engine
+            .submit_job(JobSpec {
+                from_block: 5,
+                to_block: BlockTag::Number(6),
+                kind: JobKind::StateDiffs,
+                ..Default::default()
+            })
+            .unwrap();
+        let job = wait_for(&job_registry, &job_id, |job| matches!(job.status, JobStatus::Failed(_))).await;
+        let JobStatus::Failed(err) = job.status else { unreachable!() };
+        assert!(err.contains("lags execution"), "{}", err);
+    }
+
+    #[tokio::test]
+    async fn follow_job() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path();
+        let db_path = path.join("chaindata");
+        // block 12 written but not executed yet
+        let logs = [(5, 0), (5, 1), (12, 0)];
+        write_db(&db_path, 10, &logs, None);
+        let (result_storage, job_registry, engine) = new_engine(&db_path, path);
+        engine.start_dispatcher();
+
+        let job_id = engine
+            .submit_job(JobSpec {
+                job_id: Some("follow".to_string()),
+                to_block: BlockTag::Latest,
+                follow: true,
+                ..Default::default()
+            })
+            .unwrap();
+        let job = wait_for(&job_registry, &job_id, |job| job.last_block == Some(10)).await;
+        assert_eq!((job.parts, job.rows), (1, Some(2)));
+        assert!(result_storage.part_file(&job_id, 0).exists());
+        // catch up done, slot released
+        assert_eq!(job.status, JobStatus::Running);
+
+        write_db(&db_path, 11, &logs, None);
+        let job = wait_for(&job_registry, &job_id, |job| job.last_block == Some(11)).await;
+        // empty range does not produce part
+        assert_eq!(job.parts, 1);
+        assert!(!result_storage.part_file(&job_id, 1).exists());
+
+        write_db(&db_path, 12, &logs, None);
+        let job = wait_for(&job_registry, &job_id, |job| job.last_block == Some(12)).await;
+        assert_eq!((job.parts, job.rows), (2, Some(3)));
+        assert!(result_storage.part_file(&job_id, 1).exists());
+
+        assert!(engine.stop_job(&job_id));
+        let job = wait_for(&job_registry, &job_id, |job| job.status == JobStatus::Completed).await;
+        assert_eq!(job.rows, Some(3));
+        assert!(!engine.stop_job(&job_id));
+    }
+
+    #[tokio::test]
+    async fn follow_job_reorg() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path();
+        let db_path = path.join("chaindata");
+        write_db(&db_path, 12, &[(5, 0), (11, 0), (12, 0)], None);
+        let (result_storage, job_registry, engine) = new_engine(&db_path, path);
+        engine.start_dispatcher();
+
+        let job_id = engine
+            .submit_job(JobSpec {
+                job_id: Some("reorg".to_string()),
+                to_block: BlockTag::Latest,
+                follow: true,
+                ..Default::default()
+            })
+            .unwrap();
+        let job = wait_for(&job_registry, &job_id, |job| job.last_block == Some(12)).await;
+        assert_eq!((job.parts, job.rows), (1, Some(3)));
+        assert_eq!(job.block_hashes.len(), 13);
+        let mut events = engine.subscribe(&job_id).unwrap();
+
+        // blocks 11 and 12 replaced by other branch
+        write_db(&db_path, 12, &[(5, 0), (12, 0), (12, 1)], Some(11));
+        let job = wait_for(&job_registry, &job_id, |job| job.parts == 3).await;
+        assert_eq!(job.last_block, Some(12));
+        assert_eq!(job.rows, Some(5));
+        assert!(result_storage.retraction_file(&job_id, 1).exists());
+        assert!(result_storage.part_file(&job_id, 2).exists());
+        assert_eq!(job.block_hashes.last(), Some(&(12, block_hash(12, Some(11)))));
+        assert_eq!(
+            events.recv().await.unwrap(),
+            JobEvent::Reorg {
+                fork_block: 11,
+                retracted_blocks: 2,
+                part: 1
+            }
+        );
+
+        assert!(engine.stop_job(&job_id));
+        wait_for(&job_registry, &job_id, |job| job.status == JobStatus::Completed).await;
+        assert!(result_storage.delete_result(&job_id).unwrap());
+        assert!(!result_storage.retraction_file(&job_id, 1).exists());
+    }
+
+    #[tokio::test]
+    async fn follow_job_reorg_shorter_branch() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path();
+        let db_path = path.join("chaindata");
+        write_db(&db_path, 12, &[(5, 0), (11, 0), (12, 0)], None);
+        let (result_storage, job_registry, engine) = new_engine(&db_path, path);
+        engine.start_dispatcher();
+
+        let job_id = engine
+            .submit_job(JobSpec {
+                job_id: Some("shorter".to_string()),
+                to_block: BlockTag::Latest,
+                follow: true,
+                ..Default::default()
+            })
+            .unwrap();
+        wait_for(&job_registry, &job_id, |job| job.last_block == Some(12)).await;
+
+        // block 12 unwound, other branch ends at 11
+        write_db(&db_path, 11, &[(5, 0), (11, 0)], None);
+        let job = wait_for(&job_registry, &job_id, |job| job.parts == 2).await;
+        assert_eq!((job.last_block, job.rows), (Some(11), Some(3)));
+        assert!(result_storage.retraction_file(&job_id, 1).exists());
+
+        // branch grows back to 12, block exported again
+        write_db(&db_path, 12, &[(5, 0), (11, 0), (12, 0), (12, 1)], Some(12));
+        let job = wait_for(&job_registry, &job_id, |job| job.last_block == Some(12)).await;
+        assert_eq!((job.parts, job.rows), (3, Some(5)));
+        assert!(result_storage.part_file(&job_id, 2).exists());
+        assert_eq!(job.block_hashes.last(), Some(&(12, block_hash(12, Some(12)))));
+
+        assert!(engine.stop_job(&job_id));
+        wait_for(&job_registry, &job_id, |job| job.status == JobStatus::Completed).await;
     }
 }
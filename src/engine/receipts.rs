@@ -0,0 +1,181 @@
+use crate::db::block::{read_body, read_senders, read_transactions, tx_cursor, EthTx};
+use crate::db::chain::decode_canonical;
+use crate::db::{CANONICAL_HEADER_TABLE, LOGS_TABLE, RECEIPTS_TABLE};
+use crate::engine::scan::{block_cursor, decode_log_key, Progress};
+use crate::engine::{SearchEngine, LOG_CHUNK_SIZE};
+use crate::storage::table::{ColumnData, TableWriter};
+use crate::storage::WriterOptions;
+use crate::types::KvReceipt;
+use anyhow::{anyhow, bail, Result};
+use byteorder::{BigEndian, ByteOrder};
+use ethers::types::{Address, H256};
+use ethers::utils::get_contract_address;
+use libmdbx::{Cursor, NoWriteMap, Transaction, RO};
+use log::info;
+use parquet::data_type::ByteArray;
+use serde::de::IgnoredAny;
+use std::collections::HashMap;
+use std::path::Path;
+use tokio::time::Instant;
+
+const RECEIPTS_MESSAGE_TYPE: &str = "
+              message receipts {
+                REQUIRED INT64 block_n;
+                REQUIRED INT32 tx_n;
+                REQUIRED INT32 tx_type;
+                OPTIONAL INT32 status;
+                OPTIONAL BYTE_ARRAY post_state;
+                REQUIRED INT64 cumulative_gas_used;
+                REQUIRED INT64 gas_used;
+                REQUIRED INT32 log_count;
+                OPTIONAL BYTE_ARRAY contract_created;
+              }
+";
+
+struct ReceiptRow {
+    block_n: u64,
+    tx_n: u32,
+    receipt: KvReceipt,
+    gas_used: u64,
+    log_count: u32,
+    contract_created: Option<Address>,
+}
+
+impl SearchEngine {
+    // Write receipt of every tx in range with log count from TransactionLog. Return receipts count
+    pub(super) fn export_receipts(
+        &self,
+        job_id: &str,
+        result_file: &Path,
+        options: &WriterOptions,
+        txn: &Transaction<RO, NoWriteMap>,
+        (from_block, to_block): (u64, u64),
+        progress: &Progress,
+    ) -> Result<u64> {
+        let mut writer = self.result_storage.create_table(
+            job_id,
+            result_file,
+            RECEIPTS_MESSAGE_TYPE,
+            options,
+        )?;
+        let mut logs = block_cursor(txn, LOGS_TABLE, from_block)?.peekable();
+        let mut canonical = block_cursor(txn, CANONICAL_HEADER_TABLE, from_block)?.peekable();
+        let mut txs = tx_cursor(txn)?;
+        let mut receipt_count: u64 = 0;
+        let mut chunk = Vec::with_capacity(LOG_CHUNK_SIZE);
+        for item in block_cursor(txn, RECEIPTS_TABLE, from_block)? {
+            let (key, val) = item?;
+            if key.len() != 8 {
+                bail!("Malformed receipt key");
+            }
+            let block_n = BigEndian::read_u64(&key);
+            if block_n > to_block {
+                break;
+            }
+            progress.update(self, job_id, block_n, receipt_count);
+            // logs count by tx of block, logs of blocks without receipts skipped
+            let mut log_counts: HashMap<u32, u32> = HashMap::new();
+            // errors and malformed keys taken to fail below
+            while let Some(item) = logs.next_if(|item| {
+                item.as_ref()
+                    .map_or(true, |(key, _)| decode_log_key(key).map_or(true, |(log_block, _)| log_block <= block_n))
+            }) {
+                let (key, val) = item?;
+                let (log_block, tx_index) = decode_log_key(&key)?;
+                if log_block == block_n && !val.is_empty() {
+                    let tx_logs: Vec<IgnoredAny> = serde_cbor::from_slice(&val)?;
+                    log_counts.insert(tx_index, tx_logs.len() as u32);
+                }
+            }
+            let decode_start = Instant::now();
+            let receipts: Vec<KvReceipt> = serde_cbor::from_slice(&val)?;
+            self.metrics.cbor_decode_seconds.observe(decode_start.elapsed().as_secs_f64());
+            let mut hash = None;
+            while let Some(item) = canonical.next_if(|item| {
+                item.as_ref()
+                    .map_or(true, |(key, val)| decode_canonical(key, val).map_or(true, |(n, _)| n <= block_n))
+            }) {
+                let (key, val) = item?;
+                let (canonical_block, canonical_hash) = decode_canonical(&key, &val)?;
+                if canonical_block == block_n {
+                    hash = Some(canonical_hash);
+                }
+            }
+            let created = match hash {
+                Some(hash) => created_contracts(txn, &mut txs, block_n, &hash)?,
+                None => Vec::new(),
+            };
+            if !created.is_empty() && created.len() != receipts.len() {
+                bail!("Block {} has {} receipts and {} txs", block_n, receipts.len(), created.len());
+            }
+            let mut prev_cumulative_gas = 0;
+            for (tx_n, receipt) in receipts.into_iter().enumerate() {
+                let tx_n = tx_n as u32;
+                let gas_used = receipt.cumulative_gas_used.saturating_sub(prev_cumulative_gas);
+                prev_cumulative_gas = receipt.cumulative_gas_used;
+                chunk.push(ReceiptRow {
+                    block_n,
+                    tx_n,
+                    receipt,
+                    gas_used,
+                    log_count: log_counts.get(&tx_n).copied().unwrap_or_default(),
+                    contract_created: created.get(tx_n as usize).copied().flatten(),
+                });
+            }
+            if chunk.len() > LOG_CHUNK_SIZE {
+                receipt_count += chunk.len() as u64;
+                info!("[{}] block:{} receipts count:{}", job_id, block_n, receipt_count);
+                write_receipts(&mut writer, &chunk)?;
+                chunk.clear();
+            }
+        }
+        if !chunk.is_empty() {
+            receipt_count += chunk.len() as u64;
+            write_receipts(&mut writer, &chunk)?;
+        }
+        writer.publish()?;
+        Ok(receipt_count)
+    }
+}
+
+// Address of contract created by each tx of canonical block, derived from sender and nonce.
+// Empty without body, None for calls and txs without sender
+fn created_contracts(
+    txn: &Transaction<RO, NoWriteMap>,
+    txs: &mut Cursor<'_, RO>,
+    block_n: u64,
+    hash: &H256,
+) -> Result<Vec<Option<Address>>> {
+    let Some(body) = read_body(txn, block_n, hash)? else {
+        return Ok(Vec::new());
+    };
+    let senders = read_senders(txn, block_n, hash)?;
+    let mut created = Vec::new();
+    for (tx_n, raw) in read_transactions(txs, &body)?.iter().enumerate() {
+        let nonce = EthTx::create_nonce(raw).map_err(|err| anyhow!("Block {} tx {}: {}", block_n, tx_n, err))?;
+        created.push(nonce.zip(senders.get(tx_n)).map(|(nonce, sender)| get_contract_address(*sender, nonce)));
+    }
+    Ok(created)
+}
+
+fn write_receipts(writer: &mut TableWriter, chunk: &[ReceiptRow]) -> Result<()> {
+    // pre-Byzantium receipt has state root instead of status
+    let pre_byzantium = |r: &ReceiptRow| r.receipt.post_state.as_ref().is_some_and(|s| !s.is_empty());
+    writer.write_row_group(vec![
+        ColumnData::Int64(chunk.iter().map(|r| r.block_n as i64).collect()),
+        ColumnData::Int32(chunk.iter().map(|r| r.tx_n as i32).collect()),
+        ColumnData::Int32(chunk.iter().map(|r| r.receipt.tx_type as i32).collect()),
+        ColumnData::optional_int32(
+            chunk
+                .iter()
+                .map(|r| (!pre_byzantium(r)).then_some(r.receipt.status as i32)),
+        ),
+        ColumnData::optional_bytes(chunk.iter().map(|r| {
+            pre_byzantium(r).then(|| ByteArray::from(r.receipt.post_state.clone().unwrap_or_default().to_vec()))
+        })),
+        ColumnData::Int64(chunk.iter().map(|r| r.receipt.cumulative_gas_used as i64).collect()),
+        ColumnData::Int64(chunk.iter().map(|r| r.gas_used as i64).collect()),
+        ColumnData::Int32(chunk.iter().map(|r| r.log_count as i32).collect()),
+        ColumnData::optional_bytes(chunk.iter().map(|r| r.contract_created.map(|a| ByteArray::from(a.as_bytes())))),
+    ])
+}
@@ -0,0 +1,196 @@
+use crate::db::LOGS_TABLE;
+use crate::engine::{SearchEngine, PROGRESS_INTERVAL};
+use crate::job::events::JobEvent;
+use crate::job::spec::{LogFilter, LogMatcher};
+use crate::metrics::Metrics;
+use crate::types::KvLog;
+use anyhow::{bail, Result};
+use byteorder::{BigEndian, ByteOrder};
+use libmdbx::{NoWriteMap, Transaction, RO};
+use std::cell::Cell;
+use tokio::time::Instant;
+
+// Logs of tx matched by filter
+pub struct TxLogs {
+    pub block_n: u64,
+    pub tx_index: u32,
+    pub logs: Vec<KvLog>,
+}
+
+// TransactionLog key: block number u64 BE, tx index u32 BE
+pub fn decode_log_key(key: &[u8]) -> Result<(u64, u32)> {
+    if key.len() != 12 {
+        bail!("Malformed log key");
+    }
+    Ok((BigEndian::read_u64(&key[0..8]), BigEndian::read_u32(&key[8..])))
+}
+
+pub type Cursor<'txn> = Box<dyn Iterator<Item = libmdbx::Result<(Vec<u8>, Vec<u8>)>> + Send + 'txn>;
+
+// Cursor over table with block number u64 BE key prefix, from block
+pub fn block_cursor<'txn>(txn: &'txn Transaction<RO, NoWriteMap>, table: &str, from_block: u64) -> Result<Cursor<'txn>> {
+    let db = txn.open_db(Some(table))?;
+    let cursor = txn.cursor(&db)?;
+    Ok(Box::new(cursor.into_iter_from::<Vec<u8>, Vec<u8>>(&from_block.to_be_bytes())))
+}
+
+// TransactionLog cursor over block range of progress, yields txs with logs matched by filter.
+// Reports progress of scanned blocks, also when selective filter matches nothing for long
+pub struct LogScan<'txn> {
+    cursor: Cursor<'txn>,
+    to_block: u64,
+    filter: LogMatcher,
+    metrics: &'txn Metrics,
+    engine: &'txn SearchEngine,
+    job_id: &'txn str,
+    progress: &'txn Progress,
+    // block of last cursor entry
+    block: Option<u64>,
+}
+
+impl<'txn> LogScan<'txn> {
+    pub fn new(
+        engine: &'txn SearchEngine,
+        job_id: &'txn str,
+        txn: &'txn Transaction<RO, NoWriteMap>,
+        filter: &'txn LogFilter,
+        progress: &'txn Progress,
+    ) -> Result<Self> {
+        Ok(LogScan {
+            cursor: block_cursor(txn, LOGS_TABLE, progress.from_block)?,
+            to_block: progress.to_block,
+            filter: filter.matcher(),
+            metrics: &engine.metrics,
+            engine,
+            job_id,
+            progress,
+            block: None,
+        })
+    }
+
+    fn next_tx(&mut self) -> Result<Option<TxLogs>> {
+        for item in self.cursor.by_ref() {
+            let (key, val) = item?;
+            let (block_n, tx_index) = decode_log_key(&key)?;
+            if block_n > self.to_block {
+                return Ok(None);
+            }
+            if self.block != Some(block_n) {
+                self.block = Some(block_n);
+                self.progress.scanned(self.engine, self.job_id, block_n);
+            }
+            if val.is_empty() {
+                continue;
+            }
+            let decode_start = Instant::now();
+            let mut logs: Vec<KvLog> = serde_cbor::from_slice(val.as_slice())?;
+            self.metrics.cbor_decode_seconds.observe(decode_start.elapsed().as_secs_f64());
+            self.metrics.rows_scanned.inc_by(logs.len() as u64);
+            // filter by contract address and topics
+            logs.retain(|l| self.filter.matches(l));
+            self.metrics.rows_emitted.inc_by(logs.len() as u64);
+            if !logs.is_empty() {
+                return Ok(Some(TxLogs {
+                    block_n,
+                    tx_index,
+                    logs,
+                }));
+            }
+        }
+        Ok(None)
+    }
+}
+
+impl Iterator for LogScan<'_> {
+    type Item = Result<TxLogs>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next_tx().transpose()
+    }
+}
+
+// Throttled progress events of block range scan, shared by log scan and job writing rows
+pub struct Progress {
+    from_block: u64,
+    to_block: u64,
+    started: Instant,
+    last: Cell<Instant>,
+    // rows of last update
+    rows: Cell<u64>,
+}
+
+impl Progress {
+    pub fn new(from_block: u64, to_block: u64) -> Self {
+        let now = Instant::now();
+        Progress {
+            from_block,
+            to_block,
+            started: now,
+            last: Cell::new(now),
+            rows: Cell::new(0),
+        }
+    }
+
+    pub fn started(&self) -> Instant {
+        self.started
+    }
+
+    pub fn update(&self, engine: &SearchEngine, job_id: &str, block: u64, rows: u64) {
+        self.rows.set(rows);
+        self.scanned(engine, job_id, block);
+    }
+
+    // Block reached by scan, rows unchanged since last update
+    pub fn scanned(&self, engine: &SearchEngine, job_id: &str, block: u64) {
+        if self.last.get().elapsed() < PROGRESS_INTERVAL {
+            return;
+        }
+        self.last.set(Instant::now());
+        let rows = self.rows.get();
+        let range = (self.to_block - self.from_block) as f64 + 1.0;
+        let secs = self.started.elapsed().as_secs_f64();
+        engine.events.publish(
+            job_id,
+            JobEvent::Progress {
+                block,
+                percent: block.saturating_sub(self.from_block) as f64 * 100.0 / range,
+                rows,
+                rows_per_sec: if secs > 0.0 { rows as f64 / secs } else { 0.0 },
+            },
+        );
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::engine::scan::{LogScan, Progress};
+    use crate::engine::test::{new_engine, write_db};
+    use crate::engine::PROGRESS_INTERVAL;
+    use crate::job::events::JobEvent;
+    use crate::job::spec::LogFilter;
+    use ethers::types::Address;
+    use tokio::time::Instant;
+
+    #[test]
+    fn progress_without_matches() {
+        let dir = tempfile::tempdir().unwrap();
+        let db_path = dir.path().join("chaindata");
+        write_db(&db_path, 20, &[(5, 0), (12, 0)], None);
+        let (_, _, engine) = new_engine(&db_path, dir.path());
+        engine.events.open("scan");
+        let mut events = engine.subscribe("scan").unwrap();
+
+        let filter = LogFilter {
+            addresses: vec![Address::zero()],
+            ..Default::default()
+        };
+        let progress = Progress::new(0, 20);
+        // interval passed since start
+        progress.last.set(Instant::now() - PROGRESS_INTERVAL);
+        let db_env = engine.db_env().unwrap();
+        let txn = db_env.begin_ro_txn().unwrap();
+        let scan = LogScan::new(&engine, "scan", &txn, &filter, &progress).unwrap();
+        assert_eq!(scan.count(), 0);
+        assert!(matches!(events.try_recv().unwrap(), JobEvent::Progress { block: 5, rows: 0, .. }));
+    }
+}
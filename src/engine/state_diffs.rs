@@ -0,0 +1,253 @@
+use crate::db::chain::{
+    read_stage_progress, STAGE_ACCOUNT_HISTORY_INDEX, STAGE_EXECUTION, STAGE_STORAGE_HISTORY_INDEX,
+};
+use crate::db::state::{
+    next_change_block, read_change, read_plain_state, storage_value, Account, StateChange, StateKey,
+};
+use crate::db::{ACCOUNT_CHANGESET_TABLE, STORAGE_CHANGESET_TABLE};
+use crate::engine::scan::{block_cursor, Progress};
+use crate::engine::{SearchEngine, LOG_CHUNK_SIZE};
+use crate::storage::table::{uint256_value, ColumnData, TableWriter};
+use crate::storage::WriterOptions;
+use anyhow::{bail, Result};
+use ethers::types::H256;
+use libmdbx::{NoWriteMap, Transaction, RO};
+use log::info;
+use parquet::data_type::ByteArray;
+use std::collections::HashMap;
+use std::iter::Peekable;
+use std::path::Path;
+
+const STATE_DIFFS_MESSAGE_TYPE: &str = "
+              message state_diffs {
+                REQUIRED INT64 block_n;
+                REQUIRED BYTE_ARRAY address;
+                OPTIONAL BYTE_ARRAY slot;
+                OPTIONAL BYTE_ARRAY prev_value;
+                OPTIONAL BYTE_ARRAY new_value;
+                OPTIONAL INT64 prev_nonce;
+                OPTIONAL INT64 new_nonce;
+                OPTIONAL BYTE_ARRAY prev_balance;
+                OPTIONAL BYTE_ARRAY new_balance;
+                OPTIONAL BYTE_ARRAY prev_code_hash;
+                OPTIONAL BYTE_ARRAY new_code_hash;
+              }
+";
+
+type Changes<'txn> = Peekable<Box<dyn Iterator<Item = Result<StateChange>> + 'txn>>;
+
+// Account and storage change sets from block, ordered by block, accounts of block first
+struct ChangeSetScan<'txn> {
+    accounts: Changes<'txn>,
+    storage: Changes<'txn>,
+}
+
+impl<'txn> ChangeSetScan<'txn> {
+    fn new(txn: &'txn Transaction<RO, NoWriteMap>, from_block: u64) -> Result<Self> {
+        Ok(ChangeSetScan {
+            accounts: changes(txn, ACCOUNT_CHANGESET_TABLE, from_block, StateChange::from_account_change)?,
+            storage: changes(txn, STORAGE_CHANGESET_TABLE, from_block, StateChange::from_storage_change)?,
+        })
+    }
+}
+
+impl<'txn> Iterator for ChangeSetScan<'txn> {
+    type Item = Result<StateChange>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        // errors first
+        let block = |change: Option<&Result<StateChange>>| change.map(|c| c.as_ref().map_or(0, |c| c.block_n));
+        match (block(self.accounts.peek()), block(self.storage.peek())) {
+            (Some(account_block), Some(storage_block)) if storage_block < account_block => self.storage.next(),
+            (Some(_), _) => self.accounts.next(),
+            (None, _) => self.storage.next(),
+        }
+    }
+}
+
+fn changes<'txn>(
+    txn: &'txn Transaction<RO, NoWriteMap>,
+    table: &str,
+    from_block: u64,
+    decode: fn(&[u8], &[u8]) -> Result<StateChange>,
+) -> Result<Changes<'txn>> {
+    let changes: Box<dyn Iterator<Item = Result<StateChange>> + 'txn> =
+        Box::new(block_cursor(txn, table, from_block)?.map(move |item| {
+            let (key, value) = item?;
+            decode(&key, &value)
+        }));
+    Ok(changes.peekable())
+}
+
+struct DiffRow {
+    change: StateChange,
+    // value after block, same encoding as change.prev
+    new: Vec<u8>,
+}
+
+// Changes of blocks executed but not indexed yet: previous values by key, ordered by block
+type Unindexed = HashMap<StateKey, Vec<(u64, Vec<u8>)>>;
+
+// Most blocks executed but not indexed yet scanned in change sets, longer history index lag fails job
+const MAX_INDEX_LAG: u64 = 1_000;
+
+impl SearchEngine {
+    // Write account and storage changes of blocks in range. New value of a change is previous value of
+    // next change of the key: found by history index, in change sets of blocks executed but not indexed yet,
+    // or PlainState when the key did not change since. Changes resolved and written in windows of
+    // LOG_CHUNK_SIZE rows. Return changes count
+    pub(super) fn export_state_diffs(
+        &self,
+        job_id: &str,
+        result_file: &Path,
+        options: &WriterOptions,
+        txn: &Transaction<RO, NoWriteMap>,
+        (from_block, to_block): (u64, u64),
+        progress: &Progress,
+    ) -> Result<u64> {
+        let executed = read_stage_progress(txn, STAGE_EXECUTION)?;
+        let indexed = read_stage_progress(txn, STAGE_ACCOUNT_HISTORY_INDEX)?
+            .min(read_stage_progress(txn, STAGE_STORAGE_HISTORY_INDEX)?)
+            .min(executed);
+        if executed - indexed > MAX_INDEX_LAG {
+            bail!(
+                "History index at block {} lags execution at block {} by more than {} blocks, retry when indexed",
+                indexed,
+                executed,
+                MAX_INDEX_LAG
+            );
+        }
+        let unindexed = unindexed_changes(txn, indexed + 1, executed)?;
+        let mut writer = self.result_storage.create_table(
+            job_id,
+            result_file,
+            STATE_DIFFS_MESSAGE_TYPE,
+            options,
+        )?;
+        let mut rows: u64 = 0;
+        let mut window: Vec<DiffRow> = Vec::new();
+        // row of last change by key in window, new value not known yet
+        let mut last: HashMap<StateKey, usize> = HashMap::new();
+        for change in ChangeSetScan::new(txn, from_block)? {
+            let change = change?;
+            if change.block_n > to_block {
+                break;
+            }
+            progress.update(self, job_id, change.block_n, rows);
+            if let Some(i) = last.insert(change.key, window.len()) {
+                window[i].new = change.prev.clone();
+            }
+            window.push(DiffRow {
+                change,
+                new: Vec::new(),
+            });
+            if window.len() >= LOG_CHUNK_SIZE {
+                resolve_new_values(txn, &mut window, last.drain(), indexed, &unindexed)?;
+                write_state_diffs(&mut writer, &window)?;
+                rows += window.len() as u64;
+                info!("[{}] block:{} changes count:{}", job_id, window[window.len() - 1].change.block_n, rows);
+                window.clear();
+            }
+        }
+        if !window.is_empty() {
+            resolve_new_values(txn, &mut window, last.drain(), indexed, &unindexed)?;
+            write_state_diffs(&mut writer, &window)?;
+            rows += window.len() as u64;
+        }
+        writer.publish()?;
+        Ok(rows)
+    }
+}
+
+// Changes of blocks from..=to by key
+fn unindexed_changes(txn: &Transaction<RO, NoWriteMap>, from_block: u64, to_block: u64) -> Result<Unindexed> {
+    let mut unindexed = Unindexed::new();
+    if from_block > to_block {
+        return Ok(unindexed);
+    }
+    for change in ChangeSetScan::new(txn, from_block)? {
+        let change = change?;
+        if change.block_n > to_block {
+            break;
+        }
+        unindexed.entry(change.key).or_default().push((change.block_n, change.prev));
+    }
+    Ok(unindexed)
+}
+
+// New value of last change of keys in window: previous value of next change of key after its block
+fn resolve_new_values(
+    txn: &Transaction<RO, NoWriteMap>,
+    window: &mut [DiffRow],
+    last: impl Iterator<Item = (StateKey, usize)>,
+    indexed: u64,
+    unindexed: &Unindexed,
+) -> Result<()> {
+    for (key, i) in last {
+        let block_n = window[i].change.block_n;
+        // history index has no blocks after indexed
+        let next_indexed = if block_n < indexed {
+            next_indexed_value(txn, &key, block_n)?
+        } else {
+            None
+        };
+        let next_unindexed = unindexed
+            .get(&key)
+            .and_then(|changes| changes.iter().find(|(b, _)| *b > block_n));
+        window[i].new = match (next_indexed, next_unindexed) {
+            (Some(new), _) => new,
+            (None, Some((_, new))) => new.clone(),
+            (None, None) => read_plain_state(txn, &key)?,
+        };
+    }
+    Ok(())
+}
+
+// Previous value of next indexed change of key after block. Storage history has no incarnation,
+// changes of other incarnations are skipped
+fn next_indexed_value(txn: &Transaction<RO, NoWriteMap>, key: &StateKey, after_block: u64) -> Result<Option<Vec<u8>>> {
+    let mut after_block = after_block;
+    while let Some(block_n) = next_change_block(txn, key, after_block)? {
+        if let Some(prev) = read_change(txn, key, block_n)? {
+            return Ok(Some(prev));
+        }
+        after_block = block_n;
+    }
+    Ok(None)
+}
+
+fn write_state_diffs(writer: &mut TableWriter, chunk: &[DiffRow]) -> Result<()> {
+    // (prev, new) of account rows, None if account does not exist
+    let accounts = chunk
+        .iter()
+        .map(|r| match r.change.key {
+            StateKey::Account(_) => Ok((
+                Account::decode_for_storage(&r.change.prev)?,
+                Account::decode_for_storage(&r.new)?,
+            )),
+            StateKey::Storage(..) => Ok((None, None)),
+        })
+        .collect::<Result<Vec<_>>>()?;
+    let storage = |r: &DiffRow, value: &[u8]| match r.change.key {
+        StateKey::Storage(..) => Some(ByteArray::from(storage_value(value).as_bytes())),
+        StateKey::Account(_) => None,
+    };
+    let hash = |h: &H256| ByteArray::from(h.as_bytes());
+    let code_hash = |a: &Option<Account>| a.as_ref().and_then(|a| a.code_hash.as_ref().map(hash));
+    writer.write_row_group(vec![
+        ColumnData::Int64(chunk.iter().map(|r| r.change.block_n as i64).collect()),
+        ColumnData::Bytes(chunk.iter().map(|r| ByteArray::from(r.change.key.address().as_bytes())).collect()),
+        ColumnData::optional_bytes(chunk.iter().map(|r| match &r.change.key {
+            StateKey::Storage(_, _, slot) => Some(hash(slot)),
+            StateKey::Account(_) => None,
+        })),
+        ColumnData::optional_bytes(chunk.iter().map(|r| storage(r, &r.change.prev))),
+        ColumnData::optional_bytes(chunk.iter().map(|r| storage(r, &r.new))),
+        ColumnData::optional_int64(accounts.iter().map(|(prev, _)| prev.as_ref().map(|a| a.nonce as i64))),
+        ColumnData::optional_int64(accounts.iter().map(|(_, new)| new.as_ref().map(|a| a.nonce as i64))),
+        ColumnData::optional_bytes(accounts.iter().map(|(prev, _)| prev.as_ref().map(|a| uint256_value(a.balance)))),
+        ColumnData::optional_bytes(accounts.iter().map(|(_, new)| new.as_ref().map(|a| uint256_value(a.balance)))),
+        ColumnData::optional_bytes(accounts.iter().map(|(prev, _)| code_hash(prev))),
+        ColumnData::optional_bytes(accounts.iter().map(|(_, new)| code_hash(new))),
+    ])
+}
@@ -0,0 +1,136 @@
+use crate::db::block::{read_body, read_senders, read_transactions, tx_cursor, EthTx};
+use crate::db::chain::decode_canonical;
+use crate::db::CANONICAL_HEADER_TABLE;
+use crate::engine::scan::{block_cursor, Progress};
+use crate::engine::{SearchEngine, LOG_CHUNK_SIZE};
+use crate::storage::table::{uint256_value, ColumnData, TableWriter};
+use crate::storage::WriterOptions;
+use anyhow::{anyhow, Result};
+use ethers::types::Address;
+use libmdbx::{NoWriteMap, Transaction, RO};
+use log::info;
+use parquet::data_type::ByteArray;
+use std::path::Path;
+
+const TRANSACTIONS_MESSAGE_TYPE: &str = "
+              message transactions {
+                REQUIRED INT64 block_n;
+                REQUIRED INT32 tx_n;
+                REQUIRED BYTE_ARRAY hash;
+                REQUIRED INT32 tx_type;
+                OPTIONAL INT64 chain_id;
+                REQUIRED INT64 nonce;
+                OPTIONAL BYTE_ARRAY from;
+                OPTIONAL BYTE_ARRAY to;
+                REQUIRED BYTE_ARRAY value;
+                REQUIRED INT64 gas;
+                OPTIONAL BYTE_ARRAY gas_price;
+                OPTIONAL BYTE_ARRAY max_priority_fee_per_gas;
+                OPTIONAL BYTE_ARRAY max_fee_per_gas;
+                OPTIONAL BYTE_ARRAY max_fee_per_blob_gas;
+                REQUIRED BYTE_ARRAY input;
+                REQUIRED BYTE_ARRAY access_list (UTF8);
+                REQUIRED BYTE_ARRAY blob_versioned_hashes (UTF8);
+                REQUIRED BYTE_ARRAY authorization_list (UTF8);
+              }
+";
+
+struct TxRow {
+    block_n: u64,
+    tx_n: u32,
+    from: Option<Address>,
+    tx: EthTx,
+}
+
+impl SearchEngine {
+    // Write every tx of canonical block bodies in range with sender. Return txs count
+    pub(super) fn export_transactions(
+        &self,
+        job_id: &str,
+        result_file: &Path,
+        options: &WriterOptions,
+        txn: &Transaction<RO, NoWriteMap>,
+        (from_block, to_block): (u64, u64),
+        progress: &Progress,
+    ) -> Result<u64> {
+        let mut writer = self.result_storage.create_table(
+            job_id,
+            result_file,
+            TRANSACTIONS_MESSAGE_TYPE,
+            options,
+        )?;
+        let mut tx_count: u64 = 0;
+        let mut chunk = Vec::with_capacity(LOG_CHUNK_SIZE);
+        let mut txs = tx_cursor(txn)?;
+        for item in block_cursor(txn, CANONICAL_HEADER_TABLE, from_block)? {
+            let (key, hash) = item?;
+            let (block_n, hash) = decode_canonical(&key, &hash)?;
+            if block_n > to_block {
+                break;
+            }
+            progress.update(self, job_id, block_n, tx_count);
+            let body = read_body(txn, block_n, &hash)?.ok_or_else(|| anyhow!("Missing body of block {}", block_n))?;
+            let senders = read_senders(txn, block_n, &hash)?;
+            for (tx_n, raw) in read_transactions(&mut txs, &body)?.iter().enumerate() {
+                let tx = EthTx::decode(raw).map_err(|err| anyhow!("Block {} tx {}: {}", block_n, tx_n, err))?;
+                chunk.push(TxRow {
+                    block_n,
+                    tx_n: tx_n as u32,
+                    from: senders.get(tx_n).copied(),
+                    tx,
+                });
+            }
+            if chunk.len() > LOG_CHUNK_SIZE {
+                tx_count += chunk.len() as u64;
+                info!("[{}] block:{} txs count:{}", job_id, block_n, tx_count);
+                write_transactions(&mut writer, &chunk)?;
+                chunk.clear();
+            }
+        }
+        if !chunk.is_empty() {
+            tx_count += chunk.len() as u64;
+            write_transactions(&mut writer, &chunk)?;
+        }
+        writer.publish()?;
+        Ok(tx_count)
+    }
+}
+
+fn write_transactions(writer: &mut TableWriter, chunk: &[TxRow]) -> Result<()> {
+    let address = |a: Option<Address>| a.map(|a| ByteArray::from(a.as_bytes()));
+    writer.write_row_group(vec![
+        ColumnData::Int64(chunk.iter().map(|r| r.block_n as i64).collect()),
+        ColumnData::Int32(chunk.iter().map(|r| r.tx_n as i32).collect()),
+        ColumnData::Bytes(chunk.iter().map(|r| ByteArray::from(r.tx.hash.as_bytes())).collect()),
+        ColumnData::Int32(chunk.iter().map(|r| r.tx.tx_type as i32).collect()),
+        ColumnData::optional_int64(chunk.iter().map(|r| r.tx.chain_id.map(|c| c as i64))),
+        ColumnData::Int64(chunk.iter().map(|r| r.tx.nonce as i64).collect()),
+        ColumnData::optional_bytes(chunk.iter().map(|r| address(r.from))),
+        ColumnData::optional_bytes(chunk.iter().map(|r| address(r.tx.to))),
+        ColumnData::Bytes(chunk.iter().map(|r| uint256_value(r.tx.value)).collect()),
+        ColumnData::Int64(chunk.iter().map(|r| r.tx.gas as i64).collect()),
+        ColumnData::optional_bytes(chunk.iter().map(|r| r.tx.gas_price.map(uint256_value))),
+        ColumnData::optional_bytes(chunk.iter().map(|r| r.tx.max_priority_fee_per_gas.map(uint256_value))),
+        ColumnData::optional_bytes(chunk.iter().map(|r| r.tx.max_fee_per_gas.map(uint256_value))),
+        ColumnData::optional_bytes(chunk.iter().map(|r| r.tx.max_fee_per_blob_gas.map(uint256_value))),
+        ColumnData::Bytes(chunk.iter().map(|r| ByteArray::from(r.tx.input.clone())).collect()),
+        ColumnData::Bytes(
+            chunk
+                .iter()
+                .map(|r| serde_json::to_vec(&r.tx.access_list).map(ByteArray::from))
+                .collect::<Result<_, _>>()?,
+        ),
+        ColumnData::Bytes(
+            chunk
+                .iter()
+                .map(|r| serde_json::to_vec(&r.tx.blob_versioned_hashes).map(ByteArray::from))
+                .collect::<Result<_, _>>()?,
+        ),
+        ColumnData::Bytes(
+            chunk
+                .iter()
+                .map(|r| serde_json::to_vec(&r.tx.authorization_list).map(ByteArray::from))
+                .collect::<Result<_, _>>()?,
+        ),
+    ])
+}
@@ -0,0 +1,77 @@
+use crate::decode::transfers::{token_transfers, TokenTransfer};
+use crate::engine::scan::{LogScan, Progress};
+use crate::engine::{SearchEngine, LOG_CHUNK_SIZE};
+use crate::storage::table::{uint256_value, ColumnData, TableWriter};
+use crate::storage::WriterOptions;
+use anyhow::Result;
+use log::info;
+use parquet::data_type::ByteArray;
+use std::path::Path;
+
+const TRANSFERS_MESSAGE_TYPE: &str = "
+              message token_transfers {
+                REQUIRED INT64 block_n;
+                REQUIRED INT32 tx_n;
+                REQUIRED BYTE_ARRAY standard (UTF8);
+                REQUIRED BYTE_ARRAY token;
+                REQUIRED BYTE_ARRAY from;
+                REQUIRED BYTE_ARRAY to;
+                OPTIONAL BYTE_ARRAY token_id;
+                REQUIRED BYTE_ARRAY amount;
+              }
+";
+
+impl SearchEngine {
+    // Write ERC-20/721/1155 transfers of matched logs, batch transfers unpacked. Return transfers count
+    pub(super) fn export_transfers(
+        &self,
+        job_id: &str,
+        result_file: &Path,
+        options: &WriterOptions,
+        scan: LogScan<'_>,
+        progress: &Progress,
+    ) -> Result<u64> {
+        let mut writer = self.result_storage.create_table(
+            job_id,
+            result_file,
+            TRANSFERS_MESSAGE_TYPE,
+            options,
+        )?;
+        let mut transfer_count: u64 = 0;
+        let mut chunk = Vec::with_capacity(LOG_CHUNK_SIZE);
+        for tx in scan {
+            let tx = tx?;
+            progress.update(self, job_id, tx.block_n, transfer_count);
+            for log in &tx.logs {
+                for transfer in token_transfers(log) {
+                    chunk.push((tx.block_n, tx.tx_index, transfer));
+                }
+            }
+            if chunk.len() > LOG_CHUNK_SIZE {
+                transfer_count += chunk.len() as u64;
+                info!("[{}] block:{} transfers count:{}", job_id, tx.block_n, transfer_count);
+                write_transfers(&mut writer, &chunk)?;
+                chunk.clear();
+            }
+        }
+        if !chunk.is_empty() {
+            transfer_count += chunk.len() as u64;
+            write_transfers(&mut writer, &chunk)?;
+        }
+        writer.publish()?;
+        Ok(transfer_count)
+    }
+}
+
+fn write_transfers(writer: &mut TableWriter, chunk: &[(u64, u32, TokenTransfer)]) -> Result<()> {
+    writer.write_row_group(vec![
+        ColumnData::Int64(chunk.iter().map(|(block_n, _, _)| *block_n as i64).collect()),
+        ColumnData::Int32(chunk.iter().map(|(_, tx_n, _)| *tx_n as i32).collect()),
+        ColumnData::Bytes(chunk.iter().map(|(_, _, t)| ByteArray::from(t.standard.name())).collect()),
+        ColumnData::Bytes(chunk.iter().map(|(_, _, t)| ByteArray::from(t.token.as_bytes())).collect()),
+        ColumnData::Bytes(chunk.iter().map(|(_, _, t)| ByteArray::from(t.from.as_bytes())).collect()),
+        ColumnData::Bytes(chunk.iter().map(|(_, _, t)| ByteArray::from(t.to.as_bytes())).collect()),
+        ColumnData::optional_bytes(chunk.iter().map(|(_, _, t)| t.token_id.map(uint256_value))),
+        ColumnData::Bytes(chunk.iter().map(|(_, _, t)| uint256_value(t.amount)).collect()),
+    ])
+}
@@ -0,0 +1,77 @@
+use crate::db::block::{read_body, Withdrawal};
+use crate::db::chain::decode_canonical;
+use crate::db::CANONICAL_HEADER_TABLE;
+use crate::engine::scan::{block_cursor, Progress};
+use crate::engine::{SearchEngine, LOG_CHUNK_SIZE};
+use crate::storage::table::{ColumnData, TableWriter};
+use crate::storage::WriterOptions;
+use anyhow::{anyhow, Result};
+use libmdbx::{NoWriteMap, Transaction, RO};
+use log::info;
+use parquet::data_type::ByteArray;
+use std::path::Path;
+
+const WITHDRAWALS_MESSAGE_TYPE: &str = "
+              message withdrawals {
+                REQUIRED INT64 block_n;
+                REQUIRED INT64 index;
+                REQUIRED INT64 validator_index;
+                REQUIRED BYTE_ARRAY address;
+                REQUIRED INT64 amount_gwei;
+              }
+";
+
+impl SearchEngine {
+    // Write withdrawals of canonical block bodies in range. Return withdrawals count
+    pub(super) fn export_withdrawals(
+        &self,
+        job_id: &str,
+        result_file: &Path,
+        options: &WriterOptions,
+        txn: &Transaction<RO, NoWriteMap>,
+        (from_block, to_block): (u64, u64),
+        progress: &Progress,
+    ) -> Result<u64> {
+        let mut writer = self.result_storage.create_table(
+            job_id,
+            result_file,
+            WITHDRAWALS_MESSAGE_TYPE,
+            options,
+        )?;
+        let mut withdrawal_count: u64 = 0;
+        let mut chunk = Vec::with_capacity(LOG_CHUNK_SIZE);
+        for item in block_cursor(txn, CANONICAL_HEADER_TABLE, from_block)? {
+            let (key, hash) = item?;
+            let (block_n, hash) = decode_canonical(&key, &hash)?;
+            if block_n > to_block {
+                break;
+            }
+            progress.update(self, job_id, block_n, withdrawal_count);
+            let body = read_body(txn, block_n, &hash)?
+                .ok_or_else(|| anyhow!("Missing body of block {}", block_n))?;
+            chunk.extend(body.withdrawals.into_iter().map(|w| (block_n, w)));
+            if chunk.len() > LOG_CHUNK_SIZE {
+                withdrawal_count += chunk.len() as u64;
+                info!("[{}] block:{} withdrawals count:{}", job_id, block_n, withdrawal_count);
+                write_withdrawals(&mut writer, &chunk)?;
+                chunk.clear();
+            }
+        }
+        if !chunk.is_empty() {
+            withdrawal_count += chunk.len() as u64;
+            write_withdrawals(&mut writer, &chunk)?;
+        }
+        writer.publish()?;
+        Ok(withdrawal_count)
+    }
+}
+
+fn write_withdrawals(writer: &mut TableWriter, chunk: &[(u64, Withdrawal)]) -> Result<()> {
+    writer.write_row_group(vec![
+        ColumnData::Int64(chunk.iter().map(|(block_n, _)| *block_n as i64).collect()),
+        ColumnData::Int64(chunk.iter().map(|(_, w)| w.index as i64).collect()),
+        ColumnData::Int64(chunk.iter().map(|(_, w)| w.validator_index as i64).collect()),
+        ColumnData::Bytes(chunk.iter().map(|(_, w)| ByteArray::from(w.address.as_bytes())).collect()),
+        ColumnData::Int64(chunk.iter().map(|(_, w)| w.amount_gwei as i64).collect()),
+    ])
+}
@@ -1,24 +1,61 @@
 mod types;
 
 use crate::engine::{SearchEngine};
-use ethers::types::{Address};
-use rocket::{get, routes, State};
-use std::str::FromStr;
+use crate::job::events::JobEvent;
+use crate::job::spec::{FieldError, JobSpec};
+use crate::job::{JobInfo, JobRegistry, JobStatus, RegisterError};
+use crate::metrics::Metrics;
+use crate::storage::ResultStorage;
+use rocket::data::{Limits, ToByteUnit};
+use rocket::fairing::AdHoc;
+use rocket::http::{ContentType, Status};
+use rocket::response::stream::{Event, EventStream};
+use rocket::serde::json::{json, Value};
+use rocket::{delete, get, post, routes, State};
+use std::io;
 use std::sync::Arc;
-use crate::http::types::ExecJobRequest;
+use tokio::sync::broadcast::error::RecvError;
+use tokio::task::spawn_blocking;
+use crate::http::types::{
+    ErrorResponse, ExecJobRequest, HealthCheck, HealthResponse, JobStatusResponse, SubmitJobResponse,
+};
 
 pub struct HttpApi {}
 
 impl HttpApi {
-    pub async fn new(port: u16, address: String, search_engine: Arc<SearchEngine>) {
+    pub async fn start(
+        port: u16,
+        address: String,
+        search_engine: Arc<SearchEngine>,
+        result_storage: Arc<ResultStorage>,
+        job_registry: Arc<JobRegistry>,
+        metrics: Arc<Metrics>,
+    ) {
         rocket::build()
             .configure(rocket::Config {
                 address: address.parse().unwrap(),
                 port,
+                // job spec may contain long address lists
+                limits: Limits::default().limit("string", 4.mebibytes()),
                 ..rocket::Config::default()
             })
             .manage(search_engine)
-            .mount("/", routes![exec_job])
+            .manage(result_storage)
+            .manage(job_registry)
+            .manage(metrics)
+            .attach(AdHoc::on_response("HTTP metrics", |req, res| {
+                Box::pin(async move {
+                    if let Some(metrics) = req.rocket().state::<Arc<Metrics>>() {
+                        // route pattern, not raw path: bounded label values
+                        let route = req.route().map(|r| r.uri.path()).unwrap_or("unmatched");
+                        metrics.observe_http(req.method().as_str(), route, res.status().code);
+                    }
+                })
+            }))
+            .mount(
+                "/",
+                routes![exec_job, submit_job, list_jobs, job_status, job_events, stop_job, delete_result, metrics, healthz, readyz, chain_status],
+            )
             .launch()
             .await
             .expect("Err setup");
@@ -26,22 +63,255 @@ impl HttpApi {
 }
 
 // Example: api/v1/exec-job?job_id=test_1&block_number_start=10000&block_number_end=3000000
+// block_number_end=latest - up to block executed by Erigon
 #[get("/api/v1/exec-job?<query..>")]
-async fn exec_job(search_engine: &State<Arc<SearchEngine>>, query: ExecJobRequest) -> String {
-    let _search_engine = search_engine.inner().clone();
-    let _job_id = query.job_id.clone();
-
-    tokio::spawn(async move {
-        _search_engine.execute_job(
-            query.job_id,
-            query.block_number_start,
-            query.block_number_end,
-            query
-                .contract
-                .map(|c| Address::from_str(c.as_str()).unwrap())
-        ).await.unwrap();
-    });
-
-
-    return _job_id;
+async fn exec_job(search_engine: &State<Arc<SearchEngine>>, query: ExecJobRequest) -> (Status, String) {
+    let spec = match query.into_spec() {
+        Ok(spec) => spec,
+        Err(errors) => {
+            let messages: Vec<String> = errors.iter().map(|e| format!("{}: {}", e.field, e.message)).collect();
+            return (Status::BadRequest, messages.join("; "));
+        }
+    };
+    match submit(search_engine, spec).await {
+        Ok(job_id) => (Status::Ok, job_id),
+        Err(err) => (register_error_status(&err), err.to_string()),
+    }
+}
+
+// Example: POST api/v1/jobs {"from_block":10000,"to_block":3000000,"filter":{"addresses":["0x..."]}}
+#[post("/api/v1/jobs", data = "<body>")]
+async fn submit_job(search_engine: &State<Arc<SearchEngine>>, body: String) -> (Status, Value) {
+    let spec = match JobSpec::from_json(&body) {
+        Ok(spec) => spec,
+        Err(errors) => return (Status::BadRequest, json!(ErrorResponse { errors })),
+    };
+    match submit(search_engine, spec).await {
+        Ok(job_id) => (Status::Created, json!(SubmitJobResponse { job_id })),
+        Err(err) => (
+            register_error_status(&err),
+            json!(ErrorResponse {
+                errors: vec![FieldError::new(err.field(), err.to_string())]
+            }),
+        ),
+    }
+}
+
+// Submit reads Erigon db, keep it off async workers
+async fn submit(search_engine: &Arc<SearchEngine>, spec: JobSpec) -> Result<String, RegisterError> {
+    let search_engine = search_engine.clone();
+    spawn_blocking(move || search_engine.submit_job(spec))
+        .await
+        .expect("Submit job panicked")
+}
+
+fn register_error_status(err: &RegisterError) -> Status {
+    match err {
+        RegisterError::InvalidJobId(_) | RegisterError::BeyondExecuted { .. } | RegisterError::NoForkchoice(_) => {
+            Status::BadRequest
+        }
+        RegisterError::DuplicateJobId(_) => Status::Conflict,
+        RegisterError::DbUnavailable(_) => Status::ServiceUnavailable,
+    }
+}
+
+// All known jobs including previous runs, oldest first
+#[get("/api/v1/jobs")]
+async fn list_jobs(search_engine: &State<Arc<SearchEngine>>, job_registry: &State<Arc<JobRegistry>>) -> Value {
+    let jobs: Vec<JobStatusResponse> = job_registry
+        .list()
+        .into_iter()
+        .map(|job| {
+            let position = queue_position(search_engine, &job);
+            JobStatusResponse::new(job, position)
+        })
+        .collect();
+    json!(jobs)
+}
+
+fn queue_position(search_engine: &SearchEngine, job: &JobInfo) -> Option<usize> {
+    match job.status {
+        JobStatus::Queued => search_engine.queue_position(&job.job_id),
+        _ => None,
+    }
+}
+
+// Example: api/v1/jobs/test_1
+#[get("/api/v1/jobs/<job_id>")]
+async fn job_status(
+    search_engine: &State<Arc<SearchEngine>>,
+    job_registry: &State<Arc<JobRegistry>>,
+    job_id: String,
+) -> (Status, Value) {
+    match job_registry.get(&job_id) {
+        Some(job) => {
+            let position = queue_position(search_engine, &job);
+            (Status::Ok, json!(JobStatusResponse::new(job, position)))
+        }
+        None => (
+            Status::NotFound,
+            json!(ErrorResponse {
+                errors: vec![FieldError::new("job_id", format!("Job {} not found", job_id))]
+            }),
+        ),
+    }
+}
+
+// Example: api/v1/jobs/test_1/events
+// SSE stream: current status, progress and chunk_flushed while running, ends with finished
+#[get("/api/v1/jobs/<job_id>/events")]
+async fn job_events(
+    search_engine: &State<Arc<SearchEngine>>,
+    job_registry: &State<Arc<JobRegistry>>,
+    job_id: String,
+) -> Result<EventStream![], (Status, Value)> {
+    // subscribe before status read, terminal event is not missed
+    let events = search_engine.subscribe(&job_id);
+    let Some(job) = job_registry.get(&job_id) else {
+        return Err((
+            Status::NotFound,
+            json!(ErrorResponse {
+                errors: vec![FieldError::new("job_id", format!("Job {} not found", job_id))]
+            }),
+        ));
+    };
+    let current = JobEvent::from_job(&job, queue_position(search_engine, &job));
+    Ok(EventStream! {
+        let done = current.is_terminal();
+        yield sse_event(&current);
+        if let (false, Some(mut events)) = (done, events) {
+            loop {
+                match events.recv().await {
+                    Ok(event) => {
+                        let done = event.is_terminal();
+                        yield sse_event(&event);
+                        if done {
+                            break;
+                        }
+                    }
+                    Err(RecvError::Lagged(_)) => continue,
+                    Err(RecvError::Closed) => break,
+                }
+            }
+        }
+    })
+}
+
+fn sse_event(event: &JobEvent) -> Event {
+    Event::json(event).event(event.name())
+}
+
+// Sync progress of Erigon: stages, canonical head, chain id, db size
+#[get("/api/v1/chain/status")]
+async fn chain_status(search_engine: &State<Arc<SearchEngine>>) -> (Status, Value) {
+    // walks SyncStage and CanonicalHeader cursors, keep it off async workers
+    let search_engine = search_engine.inner().clone();
+    let status = spawn_blocking(move || search_engine.chain_status())
+        .await
+        .expect("Chain status panicked");
+    match status {
+        Ok(status) => (Status::Ok, json!(status)),
+        Err(err) => (
+            Status::ServiceUnavailable,
+            json!(ErrorResponse {
+                errors: vec![FieldError::new("db", err.to_string())]
+            }),
+        ),
+    }
+}
+
+// Liveness: app can write results
+#[get("/healthz")]
+async fn healthz(result_storage: &State<Arc<ResultStorage>>) -> (Status, Value) {
+    // probe file write blocks, keep it off async workers
+    let result_storage = result_storage.inner().clone();
+    let checks = spawn_blocking(move || vec![HealthCheck::new("result_path", result_storage.check_writable())])
+        .await
+        .expect("Liveness check panicked");
+    health_response(checks)
+}
+
+// Readiness: Erigon db opens with compatible LCK file, required tables exist, app can write results
+#[get("/readyz")]
+async fn readyz(search_engine: &State<Arc<SearchEngine>>, result_storage: &State<Arc<ResultStorage>>) -> (Status, Value) {
+    // db open and probe file write block, keep them off async workers
+    let (search_engine, result_storage) = (search_engine.inner().clone(), result_storage.inner().clone());
+    let checks = spawn_blocking(move || {
+        vec![
+            HealthCheck::new("db", search_engine.check_db()),
+            HealthCheck::new("result_path", result_storage.check_writable()),
+        ]
+    })
+    .await
+    .expect("Readiness check panicked");
+    health_response(checks)
+}
+
+fn health_response(checks: Vec<HealthCheck>) -> (Status, Value) {
+    let response = HealthResponse::new(checks);
+    let status = if response.is_ok() {
+        Status::Ok
+    } else {
+        Status::ServiceUnavailable
+    };
+    (status, json!(response))
+}
+
+// Prometheus scrape endpoint
+#[get("/metrics")]
+async fn metrics(metrics: &State<Arc<Metrics>>, job_registry: &State<Arc<JobRegistry>>) -> (Status, (ContentType, String)) {
+    match metrics.render(&job_registry.list()) {
+        Ok(text) => (Status::Ok, (ContentType::Plain, text)),
+        Err(err) => (Status::InternalServerError, (ContentType::Plain, err.to_string())),
+    }
+}
+
+// Example: POST api/v1/jobs/test_1/stop
+// Follow job completes after current part
+#[post("/api/v1/jobs/<job_id>/stop")]
+async fn stop_job(
+    search_engine: &State<Arc<SearchEngine>>,
+    job_registry: &State<Arc<JobRegistry>>,
+    job_id: String,
+) -> (Status, String) {
+    if job_registry.get(&job_id).is_none() {
+        return (Status::NotFound, format!("Job {} not found", job_id));
+    }
+    if search_engine.stop_job(&job_id) {
+        (Status::Accepted, job_id)
+    } else {
+        (Status::Conflict, format!("Job {} is not a running follow job", job_id))
+    }
+}
+
+// Example: DELETE api/v1/results/test_1
+#[delete("/api/v1/results/<job_id>")]
+async fn delete_result(
+    result_storage: &State<Arc<ResultStorage>>,
+    job_registry: &State<Arc<JobRegistry>>,
+    job_id: String,
+) -> (Status, String) {
+    if let Err(err) = JobRegistry::validate_job_id(&job_id) {
+        return (Status::BadRequest, err.to_string());
+    }
+    let running = job_registry
+        .get(&job_id)
+        .is_some_and(|job| job.status == JobStatus::Running);
+    if running || result_storage.is_active(&job_id) {
+        return (Status::Conflict, format!("Job {} is still running", job_id));
+    }
+    match result_storage.delete_result(&job_id) {
+        Ok(true) => (Status::Ok, job_id),
+        Ok(false) => (Status::NotFound, format!("Result {} not found", job_id)),
+        Err(err) => (delete_error_status(&err), err.to_string()),
+    }
+}
+
+// Job id already validated: IO error of result files or writer started meanwhile
+fn delete_error_status(err: &anyhow::Error) -> Status {
+    match err.downcast_ref::<io::Error>() {
+        Some(err) if err.kind() == io::ErrorKind::NotFound => Status::NotFound,
+        Some(_) => Status::InternalServerError,
+        None => Status::Conflict,
+    }
 }
@@ -1,10 +1,163 @@
-
-use rocket::{FromForm};
+use crate::job::spec::{BlockTag, FieldError, JobSpec, LogFilter};
+use crate::job::{JobInfo, JobStatus};
+use crate::storage::ResultStorage;
+use ethers::types::Address;
+use rocket::FromForm;
+use serde::Serialize;
+use std::str::FromStr;
 
 #[derive(FromForm, Debug)]
 pub struct ExecJobRequest {
-    pub job_id: String,
+    // generated when not set
+    pub job_id: Option<String>,
     pub block_number_start: u64,
-    pub block_number_end: u64,
+    // block number or tag: latest, safe, finalized
+    pub block_number_end: String,
     pub contract: Option<String>,
+    // known event name, e.g. ERC20.Transfer
+    pub event: Option<String>,
+    pub priority: Option<i32>,
+    pub callback_url: Option<String>,
+    pub follow: Option<bool>,
+}
+
+impl ExecJobRequest {
+    pub fn into_spec(self) -> Result<JobSpec, Vec<FieldError>> {
+        let contract = self
+            .contract
+            .map(|c| Address::from_str(c.as_str()))
+            .transpose()
+            .map_err(|err| vec![FieldError::new("contract", err.to_string())])?;
+        let to_block = BlockTag::from_str(&self.block_number_end)
+            .map_err(|err| vec![FieldError::new("block_number_end", err)])?;
+        let spec = JobSpec {
+            job_id: self.job_id,
+            from_block: self.block_number_start,
+            to_block,
+            kind: Default::default(),
+            priority: self.priority.unwrap_or_default(),
+            filter: LogFilter {
+                addresses: contract.into_iter().collect(),
+                topics: vec![],
+                events: self.event.into_iter().collect(),
+            },
+            output: Default::default(),
+            callback_url: self.callback_url,
+            follow: self.follow.unwrap_or_default(),
+            decode: None,
+            aggregate: None,
+        };
+        spec.validate()?;
+        Ok(spec)
+    }
+}
+
+#[derive(Serialize, Debug)]
+pub struct SubmitJobResponse {
+    pub job_id: String,
+}
+
+#[derive(Serialize, Debug)]
+pub struct ErrorResponse {
+    pub errors: Vec<FieldError>,
+}
+
+#[derive(Serialize, Debug)]
+pub struct JobStatusResponse {
+    pub job_id: String,
+    pub status: &'static str,
+    // 1 - next to run
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub queue_position: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    pub from_block: u64,
+    pub to_block: u64,
+    pub finality: &'static str,
+    // not set for follow job, see part_prefix
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub result_file: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rows: Option<u64>,
+    pub follow: bool,
+    // follow job: prefix of part files, last block written and part files count
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub part_prefix: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_block: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub parts: Option<u32>,
+    pub created_at: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub started_at: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub finished_at: Option<u64>,
+}
+
+impl JobStatusResponse {
+    pub fn new(job: JobInfo, queue_position: Option<usize>) -> Self {
+        let error = match &job.status {
+            JobStatus::Failed(err) => Some(err.clone()),
+            _ => None,
+        };
+        JobStatusResponse {
+            job_id: job.job_id,
+            status: job.status.name(),
+            queue_position,
+            error,
+            from_block: job.spec.from_block,
+            to_block: job.to_block,
+            finality: job.finality.name(),
+            result_file: (!job.spec.follow).then(|| job.result_file.to_string_lossy().to_string()),
+            rows: job.rows,
+            follow: job.spec.follow,
+            part_prefix: job
+                .spec
+                .follow
+                .then(|| ResultStorage::part_prefix(&job.result_file).to_string_lossy().to_string()),
+            last_block: job.last_block,
+            parts: job.spec.follow.then_some(job.parts),
+            created_at: job.created_at,
+            started_at: job.started_at,
+            finished_at: job.finished_at,
+        }
+    }
+}
+
+#[derive(Serialize, Debug)]
+pub struct HealthCheck {
+    pub name: &'static str,
+    pub ok: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+impl HealthCheck {
+    pub fn new(name: &'static str, result: anyhow::Result<()>) -> Self {
+        HealthCheck {
+            name,
+            ok: result.is_ok(),
+            error: result.err().map(|err| err.to_string()),
+        }
+    }
+}
+
+#[derive(Serialize, Debug)]
+pub struct HealthResponse {
+    pub status: &'static str,
+    pub checks: Vec<HealthCheck>,
+}
+
+impl HealthResponse {
+    pub fn new(checks: Vec<HealthCheck>) -> Self {
+        let ok = checks.iter().all(|check| check.ok);
+        HealthResponse {
+            status: if ok { "ok" } else { "fail" },
+            checks,
+        }
+    }
+
+    pub fn is_ok(&self) -> bool {
+        self.status == "ok"
+    }
 }
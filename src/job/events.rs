@@ -0,0 +1,157 @@
+use crate::job::{JobInfo, JobStatus};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::Mutex;
+use tokio::sync::broadcast;
+
+// Slow subscribers skip old progress events
+const EVENTS_CAPACITY: usize = 64;
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum JobEvent {
+    Status {
+        status: &'static str,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        queue_position: Option<usize>,
+    },
+    Progress {
+        block: u64,
+        // scanned part of block range
+        percent: f64,
+        rows: u64,
+        rows_per_sec: f64,
+    },
+    ChunkFlushed {
+        block: u64,
+        chunk_rows: u64,
+        rows: u64,
+    },
+    // follow job part file published
+    PartWritten {
+        part: u32,
+        from_block: u64,
+        to_block: u64,
+        part_rows: u64,
+        rows: u64,
+    },
+    // follow job: exported blocks from fork_block not canonical anymore,
+    // retraction part written, blocks exported again
+    Reorg {
+        fork_block: u64,
+        retracted_blocks: u64,
+        part: u32,
+    },
+    Finished {
+        status: &'static str,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        error: Option<String>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        rows: Option<u64>,
+    },
+}
+
+impl JobEvent {
+    // Current state of job: status or terminal event
+    pub fn from_job(job: &JobInfo, queue_position: Option<usize>) -> Self {
+        match &job.status {
+            JobStatus::Queued | JobStatus::Running => JobEvent::Status {
+                status: job.status.name(),
+                queue_position,
+            },
+            JobStatus::Completed | JobStatus::Failed(_) => JobEvent::Finished {
+                status: job.status.name(),
+                error: match &job.status {
+                    JobStatus::Failed(err) => Some(err.clone()),
+                    _ => None,
+                },
+                rows: job.rows,
+            },
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            JobEvent::Status { .. } => "status",
+            JobEvent::Progress { .. } => "progress",
+            JobEvent::ChunkFlushed { .. } => "chunk_flushed",
+            JobEvent::PartWritten { .. } => "part_written",
+            JobEvent::Reorg { .. } => "reorg",
+            JobEvent::Finished { .. } => "finished",
+        }
+    }
+
+    pub fn is_terminal(&self) -> bool {
+        matches!(self, JobEvent::Finished { .. })
+    }
+}
+
+// Event channel per queued or running job
+#[derive(Default)]
+pub struct JobEvents {
+    channels: Mutex<HashMap<String, broadcast::Sender<JobEvent>>>,
+}
+
+impl JobEvents {
+    pub fn open(&self, job_id: &str) {
+        self.channels
+            .lock()
+            .unwrap()
+            .entry(job_id.to_string())
+            .or_insert_with(|| broadcast::channel(EVENTS_CAPACITY).0);
+    }
+
+    // None when job is not queued or running
+    pub fn subscribe(&self, job_id: &str) -> Option<broadcast::Receiver<JobEvent>> {
+        self.channels.lock().unwrap().get(job_id).map(|sender| sender.subscribe())
+    }
+
+    pub fn publish(&self, job_id: &str, event: JobEvent) {
+        if let Some(sender) = self.channels.lock().unwrap().get(job_id) {
+            // no subscribers is not an error
+            let _ = sender.send(event);
+        }
+    }
+
+    // Send terminal event, subscribers stream ends after it
+    pub fn close(&self, job_id: &str, event: JobEvent) {
+        if let Some(sender) = self.channels.lock().unwrap().remove(job_id) {
+            let _ = sender.send(event);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::job::events::{JobEvent, JobEvents};
+    use tokio::sync::broadcast::error::RecvError;
+
+    #[tokio::test]
+    async fn publish_and_close() {
+        let events = JobEvents::default();
+        assert!(events.subscribe("job_1").is_none());
+        events.open("job_1");
+        let mut rx = events.subscribe("job_1").unwrap();
+        let progress = JobEvent::Progress {
+            block: 15,
+            percent: 50.0,
+            rows: 10,
+            rows_per_sec: 100.0,
+        };
+        events.publish("job_1", progress.clone());
+        events.publish("job_2", progress.clone());
+        let finished = JobEvent::Finished {
+            status: "completed",
+            error: None,
+            rows: Some(10),
+        };
+        events.close("job_1", finished.clone());
+
+        assert_eq!(rx.recv().await.unwrap(), progress);
+        let last = rx.recv().await.unwrap();
+        assert!(last.is_terminal());
+        assert_eq!(last, finished);
+        assert!(matches!(rx.recv().await, Err(RecvError::Closed)));
+        assert!(events.subscribe("job_1").is_none());
+    }
+}
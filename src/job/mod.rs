@@ -0,0 +1,385 @@
+pub mod events;
+pub mod queue;
+pub mod spec;
+pub mod store;
+
+use crate::job::spec::{BlockTag, Finality, JobSpec};
+use crate::job::store::JobStore;
+use crate::storage::ResultStorage;
+use crate::util::unix_millis;
+use anyhow::Result;
+use ethers::types::H256;
+use log::{debug, error, info, warn};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fmt;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use ulid::Ulid;
+
+pub const MAX_JOB_ID_LEN: usize = 64;
+// Job store dir under result path
+pub const JOB_STORE_DIR: &str = ".jobs";
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum JobStatus {
+    Queued,
+    Running,
+    Completed,
+    Failed(String),
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JobInfo {
+    pub job_id: String,
+    pub spec: JobSpec,
+    // spec to_block tag resolved at submit
+    pub to_block: u64,
+    // reorg safety of exported range, resolved at submit
+    pub finality: Finality,
+    pub result_file: PathBuf,
+    pub status: JobStatus,
+    // unix time millis
+    pub created_at: u64,
+    pub started_at: Option<u64>,
+    pub finished_at: Option<u64>,
+    // rows written to result file
+    pub rows: Option<u64>,
+    // follow job: last block written to part files
+    #[serde(default)]
+    pub last_block: Option<u64>,
+    // follow job: part files written
+    #[serde(default)]
+    pub parts: u32,
+    // follow job: canonical hashes of last exported blocks, checked for reorg
+    #[serde(default)]
+    pub block_hashes: Vec<(u64, H256)>,
+}
+
+#[derive(Debug)]
+pub enum RegisterError {
+    InvalidJobId(String),
+    DuplicateJobId(String),
+    // requested block not executed by Erigon yet
+    BeyondExecuted {
+        field: &'static str,
+        block: u64,
+        executed: u64,
+    },
+    // safe or finalized tag without fork choice in Erigon db
+    NoForkchoice(BlockTag),
+    DbUnavailable(String),
+}
+
+impl JobStatus {
+    pub fn name(&self) -> &'static str {
+        match self {
+            JobStatus::Queued => "queued",
+            JobStatus::Running => "running",
+            JobStatus::Completed => "completed",
+            JobStatus::Failed(_) => "failed",
+        }
+    }
+}
+
+impl fmt::Display for RegisterError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RegisterError::InvalidJobId(reason) => write!(f, "Invalid job id: {}", reason),
+            RegisterError::DuplicateJobId(job_id) => write!(f, "Job id already exists: {}", job_id),
+            RegisterError::BeyondExecuted { field, block, executed } => {
+                write!(f, "{} {} is beyond executed block {}", field, block, executed)
+            }
+            RegisterError::NoForkchoice(tag) => write!(f, "{} block not set by consensus layer yet", tag),
+            RegisterError::DbUnavailable(err) => write!(f, "Db unavailable: {}", err),
+        }
+    }
+}
+
+impl RegisterError {
+    // Spec field caused error
+    pub fn field(&self) -> &'static str {
+        match self {
+            RegisterError::InvalidJobId(_) | RegisterError::DuplicateJobId(_) => "job_id",
+            RegisterError::BeyondExecuted { field, .. } => field,
+            RegisterError::NoForkchoice(_) => "to_block",
+            RegisterError::DbUnavailable(_) => "db",
+        }
+    }
+}
+
+impl std::error::Error for RegisterError {}
+
+// Known jobs and their result files, persisted in job store
+pub struct JobRegistry {
+    result_storage: Arc<ResultStorage>,
+    store: JobStore,
+    jobs: Mutex<HashMap<String, JobInfo>>,
+}
+
+impl JobRegistry {
+    // Load jobs of previous runs from store
+    pub fn open(result_storage: Arc<ResultStorage>, store: JobStore) -> Result<Self> {
+        let jobs: HashMap<String, JobInfo> = store
+            .load_all()?
+            .into_iter()
+            .map(|job| (job.job_id.clone(), job))
+            .collect();
+        info!("Job registry loaded {} jobs", jobs.len());
+        Ok(JobRegistry {
+            result_storage,
+            store,
+            jobs: Mutex::new(jobs),
+        })
+    }
+
+    // Jobs interrupted by restart: queued and following jobs returned to be queued again,
+    // running jobs marked failed and returned to notify their callback
+    pub fn recover(&self) -> (Vec<JobInfo>, Vec<JobInfo>) {
+        let mut queued = Vec::new();
+        let mut interrupted = Vec::new();
+        let mut jobs = self.jobs.lock().unwrap();
+        for job in jobs.values_mut() {
+            match job.status {
+                JobStatus::Queued => queued.push(job.clone()),
+                // continue after last written part
+                JobStatus::Running if job.spec.follow => {
+                    info!("[{}] Follow job resumed after block {:?}", job.job_id, job.last_block);
+                    job.status = JobStatus::Queued;
+                    self.persist(job);
+                    queued.push(job.clone());
+                }
+                JobStatus::Running => {
+                    warn!("[{}] Job interrupted by restart", job.job_id);
+                    job.status = JobStatus::Failed("Interrupted by restart".to_string());
+                    job.finished_at = Some(unix_millis());
+                    self.persist(job);
+                    interrupted.push(job.clone());
+                }
+                _ => {}
+            }
+        }
+        queued.sort_by_key(|job| job.created_at);
+        (queued, interrupted)
+    }
+
+    // Job id is used as file name: [A-Za-z0-9_-], max 64 chars
+    pub fn validate_job_id(job_id: &str) -> Result<(), RegisterError> {
+        if job_id.is_empty() {
+            return Err(RegisterError::InvalidJobId("empty".to_string()));
+        }
+        if job_id.len() > MAX_JOB_ID_LEN {
+            return Err(RegisterError::InvalidJobId(format!(
+                "longer than {} chars",
+                MAX_JOB_ID_LEN
+            )));
+        }
+        if let Some(c) = job_id
+            .chars()
+            .find(|c| !(c.is_ascii_alphanumeric() || *c == '_' || *c == '-'))
+        {
+            return Err(RegisterError::InvalidJobId(format!("unexpected char '{}'", c)));
+        }
+        Ok(())
+    }
+
+    // Register new job with resolved range end, generate ULID job id if not set
+    pub fn register(&self, spec: JobSpec, to_block: u64, finality: Finality) -> Result<JobInfo, RegisterError> {
+        let job_id = spec.job_id.clone().unwrap_or_else(|| Ulid::new().to_string());
+        Self::validate_job_id(&job_id)?;
+
+        let mut jobs = self.jobs.lock().unwrap();
+        let result_file = self.result_storage.result_file(&job_id);
+        if jobs.contains_key(&job_id) || result_file.exists() || self.result_storage.is_active(&job_id) {
+            return Err(RegisterError::DuplicateJobId(job_id));
+        }
+        let job = JobInfo {
+            job_id: job_id.clone(),
+            spec,
+            to_block,
+            finality,
+            result_file,
+            status: JobStatus::Queued,
+            created_at: unix_millis(),
+            started_at: None,
+            finished_at: None,
+            rows: None,
+            last_block: None,
+            parts: 0,
+            block_hashes: Vec::new(),
+        };
+        self.persist(&job);
+        jobs.insert(job_id, job.clone());
+        Ok(job)
+    }
+
+    pub fn get(&self, job_id: &str) -> Option<JobInfo> {
+        self.jobs.lock().unwrap().get(job_id).cloned()
+    }
+
+    // All jobs, oldest first
+    pub fn list(&self) -> Vec<JobInfo> {
+        let mut jobs: Vec<JobInfo> = self.jobs.lock().unwrap().values().cloned().collect();
+        jobs.sort_by_key(|job| job.created_at);
+        jobs
+    }
+
+    pub fn start(&self, job_id: &str) {
+        self.update(job_id, |job| {
+            job.status = JobStatus::Running;
+            job.started_at = Some(unix_millis());
+        });
+    }
+
+    // Part file of follow job published or exported blocks retracted
+    pub fn follow_progress(
+        &self,
+        job_id: &str,
+        last_block: Option<u64>,
+        parts: u32,
+        rows: u64,
+        block_hashes: &[(u64, H256)],
+    ) {
+        self.update(job_id, |job| {
+            job.last_block = last_block;
+            job.parts = parts;
+            job.rows = Some(rows);
+            job.block_hashes = block_hashes.to_vec();
+        });
+    }
+
+    pub fn finish(&self, job_id: &str, result: &Result<u64>) {
+        self.update(job_id, |job| {
+            match result {
+                Ok(rows) => {
+                    job.status = JobStatus::Completed;
+                    job.rows = Some(*rows);
+                }
+                Err(err) => job.status = JobStatus::Failed(err.to_string()),
+            }
+            job.finished_at = Some(unix_millis());
+        });
+    }
+
+    fn update(&self, job_id: &str, f: impl FnOnce(&mut JobInfo)) {
+        if let Some(job) = self.jobs.lock().unwrap().get_mut(job_id) {
+            let status = job.status.clone();
+            f(job);
+            // follow job updates progress on every poll
+            if job.status != status {
+                info!("[{}] Job status:{:?}", job_id, job.status);
+            } else {
+                debug!("[{}] Job updated, status:{:?}", job_id, job.status);
+            }
+            self.persist(job);
+        }
+    }
+
+    // Store failure does not stop the job, registry in memory stays valid
+    fn persist(&self, job: &JobInfo) {
+        if let Err(err) = self.store.put(job) {
+            error!("[{}] Job store err:{}", job.job_id, err);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::job::spec::{Finality, JobSpec};
+    use crate::job::store::JobStore;
+    use crate::job::{JobRegistry, JobStatus, RegisterError, JOB_STORE_DIR};
+    use crate::metrics::Metrics;
+    use crate::storage::ResultStorage;
+    use ethers::types::H256;
+    use std::fs;
+    use std::path::Path;
+    use std::sync::Arc;
+
+    #[test]
+    fn validate_job_id() {
+        assert!(JobRegistry::validate_job_id("test_1-A").is_ok());
+        assert!(JobRegistry::validate_job_id("").is_err());
+        assert!(JobRegistry::validate_job_id("../../etc/x").is_err());
+        assert!(JobRegistry::validate_job_id("a/b").is_err());
+        assert!(JobRegistry::validate_job_id("a.parquet").is_err());
+        assert!(JobRegistry::validate_job_id(&"a".repeat(65)).is_err());
+    }
+
+    fn open_registry(result_path: &Path) -> (Arc<ResultStorage>, JobRegistry) {
+        let storage = Arc::new(ResultStorage::new(result_path.to_path_buf(), Arc::new(Metrics::new())));
+        let store = JobStore::open(&result_path.join(JOB_STORE_DIR)).unwrap();
+        let registry = JobRegistry::open(storage.clone(), store).unwrap();
+        (storage, registry)
+    }
+
+    fn spec(job_id: Option<&str>) -> JobSpec {
+        JobSpec {
+            job_id: job_id.map(|id| id.to_string()),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn register() {
+        let dir = tempfile::tempdir().unwrap();
+        let result_path = dir.path();
+        let (storage, registry) = open_registry(result_path);
+
+        let job = registry.register(spec(Some("job_1")), 0, Finality::Unfinalized).unwrap();
+        assert_eq!(job.result_file, result_path.join("job_1.parquet"));
+        assert!(matches!(
+            registry.register(spec(Some("job_1")), 0, Finality::Unfinalized),
+            Err(RegisterError::DuplicateJobId(_))
+        ));
+        // result left by previous run
+        fs::write(storage.result_file("job_2"), b"PAR1").unwrap();
+        assert!(matches!(
+            registry.register(spec(Some("job_2")), 0, Finality::Unfinalized),
+            Err(RegisterError::DuplicateJobId(_))
+        ));
+        let generated = registry.register(spec(None), 0, Finality::Unfinalized).unwrap();
+        assert_eq!(generated.job_id.len(), 26);
+        assert!(registry.get(&generated.job_id).is_some());
+    }
+
+    #[test]
+    fn recover_after_restart() {
+        let dir = tempfile::tempdir().unwrap();
+        let result_path = dir.path();
+        {
+            let (_, registry) = open_registry(result_path);
+            registry.register(spec(Some("done")), 0, Finality::Unfinalized).unwrap();
+            registry.start("done");
+            registry.finish("done", &Ok(42));
+            registry.register(spec(Some("running")), 0, Finality::Unfinalized).unwrap();
+            registry.start("running");
+            registry.register(spec(Some("queued")), 0, Finality::Unfinalized).unwrap();
+            let mut follow = spec(Some("follow"));
+            follow.follow = true;
+            registry.register(follow, 0, Finality::Unfinalized).unwrap();
+            registry.start("follow");
+            registry.follow_progress("follow", Some(120), 2, 7, &[(120, H256::from_low_u64_be(1))]);
+        }
+
+        let (_, registry) = open_registry(result_path);
+        assert_eq!(registry.list().len(), 4);
+        let (mut queued, interrupted) = registry.recover();
+        queued.sort_by(|a, b| a.job_id.cmp(&b.job_id));
+        let queued_ids: Vec<&str> = queued.iter().map(|job| job.job_id.as_str()).collect();
+        assert_eq!(queued_ids, vec!["follow", "queued"]);
+        assert_eq!((queued[0].last_block, queued[0].parts, queued[0].rows), (Some(120), 2, Some(7)));
+        assert_eq!(queued[0].block_hashes, vec![(120, H256::from_low_u64_be(1))]);
+        assert_eq!(registry.get("follow").unwrap().status, JobStatus::Queued);
+        let done = registry.get("done").unwrap();
+        assert_eq!(done.status, JobStatus::Completed);
+        assert_eq!(done.rows, Some(42));
+        assert!(matches!(registry.get("running").unwrap().status, JobStatus::Failed(_)));
+        let interrupted_ids: Vec<&str> = interrupted.iter().map(|job| job.job_id.as_str()).collect();
+        assert_eq!(interrupted_ids, vec!["running"]);
+        // job ids of previous runs still taken
+        assert!(matches!(
+            registry.register(spec(Some("done")), 0, Finality::Unfinalized),
+            Err(RegisterError::DuplicateJobId(_))
+        ));
+    }
+}
@@ -0,0 +1,143 @@
+use crate::job::JobInfo;
+use std::cmp::Ordering;
+use std::collections::BinaryHeap;
+use std::sync::atomic::{AtomicU64, Ordering as AtomicOrdering};
+use std::sync::{Arc, Mutex};
+use tokio::sync::{Notify, OwnedSemaphorePermit, Semaphore};
+
+pub struct QueuedJob {
+    pub job: JobInfo,
+    // submit order, FIFO for same priority
+    seq: u64,
+}
+
+impl QueuedJob {
+    fn priority(&self) -> i32 {
+        self.job.spec.priority
+    }
+}
+
+// Greater job runs first: higher priority, then earlier submit
+impl Ord for QueuedJob {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.priority()
+            .cmp(&other.priority())
+            .then_with(|| other.seq.cmp(&self.seq))
+    }
+}
+
+impl PartialOrd for QueuedJob {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl PartialEq for QueuedJob {
+    fn eq(&self, other: &Self) -> bool {
+        self.seq == other.seq
+    }
+}
+
+impl Eq for QueuedJob {}
+
+// Pending jobs ordered by priority, running jobs limited by slots
+pub struct JobQueue {
+    pending: Mutex<BinaryHeap<QueuedJob>>,
+    notify: Notify,
+    slots: Arc<Semaphore>,
+    seq: AtomicU64,
+}
+
+impl JobQueue {
+    pub fn new(max_concurrent_jobs: usize) -> Self {
+        JobQueue {
+            pending: Mutex::new(BinaryHeap::new()),
+            notify: Notify::new(),
+            slots: Arc::new(Semaphore::new(max_concurrent_jobs.max(1))),
+            seq: AtomicU64::new(0),
+        }
+    }
+
+    // Return position in queue, 1 - next to run
+    pub fn push(&self, job: JobInfo) -> usize {
+        let seq = self.seq.fetch_add(1, AtomicOrdering::SeqCst);
+        let job_id = job.job_id.clone();
+        self.pending.lock().unwrap().push(QueuedJob { job, seq });
+        self.notify.notify_one();
+        self.position(&job_id).unwrap_or_default()
+    }
+
+    pub fn position(&self, job_id: &str) -> Option<usize> {
+        let pending = self.pending.lock().unwrap();
+        let mut jobs: Vec<&QueuedJob> = pending.iter().collect();
+        jobs.sort_by(|a, b| b.cmp(a));
+        jobs.iter().position(|q| q.job.job_id == job_id).map(|i| i + 1)
+    }
+
+    // Wait free slot and next job, slot released when permit dropped
+    pub async fn next(&self) -> (OwnedSemaphorePermit, QueuedJob) {
+        let permit = self.slots.clone().acquire_owned().await.unwrap();
+        loop {
+            if let Some(job) = self.pending.lock().unwrap().pop() {
+                return (permit, job);
+            }
+            self.notify.notified().await;
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::job::queue::JobQueue;
+    use crate::job::spec::{Finality, JobSpec};
+    use crate::job::{JobInfo, JobStatus};
+    use std::path::PathBuf;
+    use std::time::Duration;
+    use tokio::time::timeout;
+
+    fn job(job_id: &str, priority: i32) -> JobInfo {
+        JobInfo {
+            job_id: job_id.to_string(),
+            spec: JobSpec {
+                priority,
+                ..Default::default()
+            },
+            to_block: 0,
+            finality: Finality::Unfinalized,
+            result_file: PathBuf::from(job_id),
+            status: JobStatus::Queued,
+            created_at: 0,
+            started_at: None,
+            finished_at: None,
+            rows: None,
+            last_block: None,
+            parts: 0,
+            block_hashes: Vec::new(),
+        }
+    }
+
+    #[tokio::test]
+    async fn priority_and_concurrency() {
+        let queue = JobQueue::new(2);
+        for (job_id, priority) in [("a", 0), ("b", 0), ("c", 5), ("d", 0)] {
+            queue.push(job(job_id, priority));
+        }
+        assert_eq!(queue.position("c"), Some(1));
+        assert_eq!(queue.position("a"), Some(2));
+        assert_eq!(queue.position("d"), Some(4));
+        assert_eq!(queue.position("x"), None);
+
+        let (permit_1, first) = queue.next().await;
+        let (_permit_2, second) = queue.next().await;
+        assert_eq!(first.job.job_id, "c");
+        assert_eq!(second.job.job_id, "a");
+        assert_eq!(queue.position("b"), Some(1));
+
+        // both slots busy
+        assert!(timeout(Duration::from_millis(50), queue.next()).await.is_err());
+        drop(permit_1);
+        let (_permit_3, third) = queue.next().await;
+        assert_eq!(third.job.job_id, "b");
+        assert_eq!(queue.position("d"), Some(1));
+    }
+}
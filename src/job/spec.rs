@@ -0,0 +1,601 @@
+use crate::decode::registry::registry;
+use crate::decode::EventDecoder;
+use crate::job::JobRegistry;
+use crate::types::KvLog;
+use ethers::types::{Address, H256};
+use parquet::basic::{Compression, ZstdLevel};
+use serde::de::{Error as DeError, Visitor};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::collections::HashSet;
+use std::fmt;
+use std::str::FromStr;
+
+pub const MAX_TOPICS: usize = 4;
+pub const MAX_FILTER_ITEMS: usize = 10_000;
+
+// Job description accepted by POST api/v1/jobs
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct JobSpec {
+    // generated when not set
+    #[serde(default)]
+    pub job_id: Option<String>,
+    pub from_block: u64,
+    // tag resolved to block number at submit, see JobInfo
+    pub to_block: BlockTag,
+    // output of job
+    #[serde(default)]
+    pub kind: JobKind,
+    // higher runs first, same priority in submit order
+    #[serde(default)]
+    pub priority: i32,
+    #[serde(default)]
+    pub filter: LogFilter,
+    #[serde(default)]
+    pub output: OutputOptions,
+    // http(s) url notified when job completed or failed
+    #[serde(default)]
+    pub callback_url: Option<String>,
+    // after to_block keep appending newly executed blocks as part files until stopped
+    #[serde(default)]
+    pub follow: bool,
+    // ABI decoding of matched logs into per-event files
+    #[serde(default)]
+    pub decode: Option<DecodeSpec>,
+    // group-by of aggregate job
+    #[serde(default)]
+    pub aggregate: Option<AggregateSpec>,
+}
+
+// Block number or tag relative to synced chain
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BlockTag {
+    Number(u64),
+    // highest block executed by Erigon
+    Latest,
+    // fork choice blocks of consensus layer
+    Safe,
+    Finalized,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum JobKind {
+    // matched log rows
+    #[default]
+    Logs,
+    // (contract, topic0, op_code) counts of matched logs
+    EventDiscovery,
+    // log count and data bytes of matched logs by group
+    Aggregate,
+    // normalized ERC-20/721/1155 transfers of matched logs
+    TokenTransfers,
+    // receipt of every tx in range
+    Receipts,
+    // every tx of block bodies in range
+    Transactions,
+    // canonical block headers in range
+    Blocks,
+    // beacon withdrawals of block bodies in range
+    Withdrawals,
+    // account and storage changes of blocks in range with previous and new values
+    StateDiffs,
+}
+
+impl JobKind {
+    pub fn name(&self) -> &'static str {
+        match self {
+            JobKind::Logs => "logs",
+            JobKind::EventDiscovery => "event_discovery",
+            JobKind::Aggregate => "aggregate",
+            JobKind::TokenTransfers => "token_transfers",
+            JobKind::Receipts => "receipts",
+            JobKind::Transactions => "transactions",
+            JobKind::Blocks => "blocks",
+            JobKind::Withdrawals => "withdrawals",
+            JobKind::StateDiffs => "state_diffs",
+        }
+    }
+
+    // Job reads TransactionLog, log filter applies
+    pub fn scans_logs(&self) -> bool {
+        matches!(self, JobKind::Logs | JobKind::EventDiscovery | JobKind::Aggregate | JobKind::TokenTransfers)
+    }
+}
+
+// Reorg safety of exported blocks, recorded in result metadata
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Finality {
+    // may be unwound by reorg
+    #[default]
+    Unfinalized,
+    Safe,
+    Finalized,
+}
+
+impl Finality {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Finality::Unfinalized => "unfinalized",
+            Finality::Safe => "safe",
+            Finality::Finalized => "finalized",
+        }
+    }
+
+    // Finality of range ending at block
+    pub fn of_block(block: u64, safe: Option<u64>, finalized: Option<u64>) -> Self {
+        if finalized.is_some_and(|f| block <= f) {
+            Finality::Finalized
+        } else if safe.is_some_and(|s| block <= s) {
+            Finality::Safe
+        } else {
+            Finality::Unfinalized
+        }
+    }
+}
+
+impl Default for BlockTag {
+    fn default() -> Self {
+        BlockTag::Number(0)
+    }
+}
+
+impl BlockTag {
+    pub fn number(&self) -> Option<u64> {
+        match self {
+            BlockTag::Number(n) => Some(*n),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for BlockTag {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BlockTag::Number(n) => write!(f, "{}", n),
+            BlockTag::Latest => write!(f, "latest"),
+            BlockTag::Safe => write!(f, "safe"),
+            BlockTag::Finalized => write!(f, "finalized"),
+        }
+    }
+}
+
+impl FromStr for BlockTag {
+    type Err = String;
+
+    // Decimal or 0x hex number, or tag name
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let number = match s.strip_prefix("0x") {
+            Some(hex) => u64::from_str_radix(hex, 16),
+            None => s.parse(),
+        };
+        match (s, number) {
+            (_, Ok(n)) => Ok(BlockTag::Number(n)),
+            ("latest", _) => Ok(BlockTag::Latest),
+            ("safe", _) => Ok(BlockTag::Safe),
+            ("finalized", _) => Ok(BlockTag::Finalized),
+            _ => Err(format!(
+                "expected block number, \"latest\", \"safe\" or \"finalized\", got \"{}\"",
+                s
+            )),
+        }
+    }
+}
+
+impl Serialize for BlockTag {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            BlockTag::Number(n) => serializer.serialize_u64(*n),
+            tag => serializer.serialize_str(&tag.to_string()),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for BlockTag {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct BlockTagVisitor;
+
+        impl Visitor<'_> for BlockTagVisitor {
+            type Value = BlockTag;
+
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                f.write_str("block number, \"latest\", \"safe\" or \"finalized\"")
+            }
+
+            fn visit_u64<E: DeError>(self, v: u64) -> Result<BlockTag, E> {
+                Ok(BlockTag::Number(v))
+            }
+
+            fn visit_str<E: DeError>(self, v: &str) -> Result<BlockTag, E> {
+                BlockTag::from_str(v).map_err(E::custom)
+            }
+        }
+
+        deserializer.deserialize_any(BlockTagVisitor)
+    }
+}
+
+// Same semantic as eth_getLogs: empty list matches any value
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct LogFilter {
+    #[serde(default)]
+    pub addresses: Vec<Address>,
+    // topics[i] - accepted values of topic i
+    #[serde(default)]
+    pub topics: Vec<Vec<H256>>,
+    // known event names, e.g. ERC20.Transfer, matched by topic0 and topics count
+    #[serde(default)]
+    pub events: Vec<String>,
+}
+
+// Filter prepared for scan: lookup sets built once, empty set matches any value
+pub struct LogMatcher {
+    addresses: HashSet<Address>,
+    topics: Vec<HashSet<H256>>,
+    // topic0 and topics count of known events, None: any event
+    events: Option<HashSet<(H256, usize)>>,
+}
+
+// Events decoded from JSON ABI and human readable signatures
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct DecodeSpec {
+    // JSON ABI, only events used
+    #[serde(default)]
+    pub abi: Option<serde_json::Value>,
+    // "event Transfer(address indexed from, address indexed to, uint256 value)"
+    #[serde(default)]
+    pub events: Vec<String>,
+}
+
+// Group-by columns of aggregate job, no group-by: totals of range
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct AggregateSpec {
+    #[serde(default)]
+    pub group_by: Vec<GroupBy>,
+    // blocks per bucket of block group, e.g. 7200 ~ day
+    #[serde(default = "default_block_bucket")]
+    pub block_bucket: u64,
+}
+
+fn default_block_bucket() -> u64 {
+    1
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum GroupBy {
+    Contract,
+    Topic0,
+    // first block of bucket
+    Block,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct OutputOptions {
+    #[serde(default)]
+    pub compression: OutputCompression,
+}
+
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum OutputCompression {
+    #[default]
+    Snappy,
+    Zstd,
+    None,
+}
+
+impl OutputCompression {
+    pub fn to_parquet(self) -> Compression {
+        match self {
+            OutputCompression::Snappy => Compression::SNAPPY,
+            OutputCompression::Zstd => Compression::ZSTD(ZstdLevel::default()),
+            OutputCompression::None => Compression::UNCOMPRESSED,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct FieldError {
+    pub field: String,
+    pub message: String,
+}
+
+impl FieldError {
+    pub fn new(field: &str, message: impl Into<String>) -> Self {
+        FieldError {
+            field: field.to_string(),
+            message: message.into(),
+        }
+    }
+}
+
+impl JobSpec {
+    // Parse JSON body, type errors reported with path of the field
+    pub fn from_json(body: &str) -> Result<JobSpec, Vec<FieldError>> {
+        let de = &mut serde_json::Deserializer::from_str(body);
+        let spec: JobSpec = serde_path_to_error::deserialize(de).map_err(|err| {
+            let field = err.path().to_string();
+            vec![FieldError::new(&field, err.into_inner().to_string())]
+        })?;
+        spec.validate()?;
+        Ok(spec)
+    }
+
+    pub fn validate(&self) -> Result<(), Vec<FieldError>> {
+        let mut errors = Vec::new();
+        if let Some(job_id) = &self.job_id {
+            if let Err(err) = JobRegistry::validate_job_id(job_id) {
+                errors.push(FieldError::new("job_id", err.to_string()));
+            }
+        }
+        if self.to_block.number().is_some_and(|to_block| self.from_block > to_block) {
+            errors.push(FieldError::new("to_block", "must be greater or equal to from_block"));
+        }
+        if self.filter.addresses.len() > MAX_FILTER_ITEMS {
+            errors.push(FieldError::new(
+                "filter.addresses",
+                format!("more than {} addresses", MAX_FILTER_ITEMS),
+            ));
+        }
+        if self.filter.topics.len() > MAX_TOPICS {
+            errors.push(FieldError::new(
+                "filter.topics",
+                format!("more than {} topic positions", MAX_TOPICS),
+            ));
+        }
+        for (i, topics) in self.filter.topics.iter().enumerate() {
+            if topics.len() > MAX_FILTER_ITEMS {
+                errors.push(FieldError::new(
+                    &format!("filter.topics[{}]", i),
+                    format!("more than {} topics", MAX_FILTER_ITEMS),
+                ));
+            }
+        }
+        for (i, name) in self.filter.events.iter().enumerate() {
+            if registry().get(name).is_none() {
+                errors.push(FieldError::new(
+                    &format!("filter.events[{}]", i),
+                    format!("unknown event {}", name),
+                ));
+            }
+        }
+        if !self.kind.scans_logs() && !self.filter.is_empty() {
+            errors.push(FieldError::new("filter", format!("not supported by {} job", self.kind.name())));
+        }
+        if self.kind != JobKind::Logs {
+            if self.follow {
+                errors.push(FieldError::new("follow", format!("not supported by {} job", self.kind.name())));
+            }
+            if self.decode.is_some() {
+                errors.push(FieldError::new("decode", format!("not supported by {} job", self.kind.name())));
+            }
+        }
+        match (&self.aggregate, self.kind) {
+            (None, JobKind::Aggregate) => errors.push(FieldError::new("aggregate", "required by aggregate job")),
+            (Some(_), kind) if kind != JobKind::Aggregate => {
+                errors.push(FieldError::new("aggregate", format!("not supported by {} job", kind.name())))
+            }
+            (Some(aggregate), _) => {
+                if aggregate.block_bucket == 0 {
+                    errors.push(FieldError::new("aggregate.block_bucket", "must be greater than 0"));
+                }
+                for (i, group_by) in aggregate.group_by.iter().enumerate() {
+                    if aggregate.group_by[..i].contains(group_by) {
+                        errors.push(FieldError::new(&format!("aggregate.group_by[{}]", i), "duplicate group-by"));
+                    }
+                }
+            }
+            _ => {}
+        }
+        if let Some(decode) = &self.decode {
+            if let Err(err) = EventDecoder::new(decode) {
+                errors.push(FieldError::new("decode", err.to_string()));
+            }
+        }
+        if let Some(url) = &self.callback_url {
+            match reqwest::Url::parse(url) {
+                Ok(url) if url.scheme() == "http" || url.scheme() == "https" => {}
+                Ok(_) => errors.push(FieldError::new("callback_url", "scheme must be http or https")),
+                Err(err) => errors.push(FieldError::new("callback_url", err.to_string())),
+            }
+        }
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
+}
+
+impl LogFilter {
+    pub fn is_empty(&self) -> bool {
+        self.addresses.is_empty() && self.topics.is_empty() && self.events.is_empty()
+    }
+
+    pub fn matcher(&self) -> LogMatcher {
+        let events = (!self.events.is_empty()).then(|| {
+            self.events
+                .iter()
+                .filter_map(|name| registry().get(name))
+                .map(|event| (event.topic0, event.topics_len))
+                .collect()
+        });
+        LogMatcher {
+            addresses: self.addresses.iter().copied().collect(),
+            topics: self.topics.iter().map(|accepted| accepted.iter().copied().collect()).collect(),
+            events,
+        }
+    }
+}
+
+impl LogMatcher {
+    pub fn matches(&self, log: &KvLog) -> bool {
+        if !self.addresses.is_empty() && !self.addresses.contains(&log.address) {
+            return false;
+        }
+        if let Some(events) = &self.events {
+            let known_event = log.topics.first().is_some_and(|topic0| events.contains(&(*topic0, log.topics.len())));
+            if !known_event {
+                return false;
+            }
+        }
+        self.topics.iter().enumerate().all(|(i, accepted)| {
+            accepted.is_empty() || log.topics.get(i).is_some_and(|topic| accepted.contains(topic))
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::decode::registry::registry;
+    use crate::job::spec::{BlockTag, FieldError, GroupBy, JobKind, JobSpec, LogFilter};
+    use crate::types::KvLog;
+    use ethers::types::{Address, H256};
+
+    #[test]
+    fn from_json() {
+        let spec = JobSpec::from_json(
+            r#"{
+                "job_id": "job_1",
+                "from_block": 10,
+                "to_block": 20,
+                "priority": 10,
+                "filter": {"addresses": ["0x0000000000000000000000000000000000000001"]},
+                "output": {"compression": "zstd"}
+            }"#,
+        )
+        .unwrap();
+        assert_eq!(spec.filter.addresses, vec![Address::from_low_u64_be(1)]);
+
+        let errors = JobSpec::from_json(r#"{"from_block": "x", "to_block": 20}"#).unwrap_err();
+        assert_eq!(errors[0].field, "from_block");
+
+        let errors = JobSpec::from_json(r#"{"from_block": 1, "to_block": 2, "filter": {"addresses": ["0x1"]}}"#)
+            .unwrap_err();
+        assert_eq!(errors[0].field, "filter.addresses[0]");
+
+        let errors = JobSpec::from_json(r#"{"job_id": "../x", "from_block": 30, "to_block": 20}"#).unwrap_err();
+        assert_eq!(errors.len(), 2);
+        assert_eq!(errors[0].field, "job_id");
+        assert_eq!(
+            errors[1],
+            FieldError::new("to_block", "must be greater or equal to from_block")
+        );
+
+        let spec = JobSpec::from_json(r#"{"from_block": 1, "to_block": "latest"}"#).unwrap();
+        assert_eq!(spec.to_block, BlockTag::Latest);
+        let spec = JobSpec::from_json(r#"{"from_block": 1, "to_block": "0x10"}"#).unwrap();
+        assert_eq!(spec.to_block, BlockTag::Number(16));
+        let spec = JobSpec::from_json(r#"{"from_block": 1, "to_block": "finalized"}"#).unwrap();
+        assert_eq!(spec.to_block, BlockTag::Finalized);
+        assert_eq!("safe".parse::<BlockTag>(), Ok(BlockTag::Safe));
+        let errors = JobSpec::from_json(r#"{"from_block": 1, "to_block": "head"}"#).unwrap_err();
+        assert_eq!(errors[0].field, "to_block");
+        // stored specs keep numbers
+        assert_eq!(serde_cbor::to_vec(&BlockTag::Number(16)).unwrap(), serde_cbor::to_vec(&16u64).unwrap());
+
+        let spec = JobSpec::from_json(r#"{"from_block": 1, "to_block": 2, "callback_url": "http://localhost:8080/done"}"#);
+        assert!(spec.is_ok());
+        let errors =
+            JobSpec::from_json(r#"{"from_block": 1, "to_block": 2, "callback_url": "ftp://host/done"}"#).unwrap_err();
+        assert_eq!(errors[0].field, "callback_url");
+
+        let spec = JobSpec::from_json(
+            r#"{"from_block": 1, "to_block": 2, "decode": {"events": ["event Approval(address indexed owner, address indexed spender, uint256 value)"]}}"#,
+        );
+        assert!(spec.is_ok());
+        let errors =
+            JobSpec::from_json(r#"{"from_block": 1, "to_block": 2, "decode": {"abi": [{"type": "event"}]}}"#).unwrap_err();
+        assert_eq!(errors[0].field, "decode");
+
+        let errors = JobSpec::from_json(r#"{"from_block": 1, "to_block": 2, "filter": {"events": ["ERC20.Transfer", "ERC20.Mint"]}}"#)
+            .unwrap_err();
+        assert_eq!(errors, vec![FieldError::new("filter.events[1]", "unknown event ERC20.Mint")]);
+
+        let spec = JobSpec::from_json(r#"{"from_block": 1, "to_block": 2, "kind": "event_discovery"}"#).unwrap();
+        assert_eq!(spec.kind, JobKind::EventDiscovery);
+        let errors = JobSpec::from_json(r#"{"from_block": 1, "to_block": 2, "kind": "event_discovery", "follow": true}"#)
+            .unwrap_err();
+        assert_eq!(errors, vec![FieldError::new("follow", "not supported by event_discovery job")]);
+
+        let spec = JobSpec::from_json(
+            r#"{"from_block": 1, "to_block": 2, "kind": "aggregate", "aggregate": {"group_by": ["contract", "block"]}}"#,
+        )
+        .unwrap();
+        let aggregate = spec.aggregate.unwrap();
+        assert_eq!(aggregate.group_by, vec![GroupBy::Contract, GroupBy::Block]);
+        assert_eq!(aggregate.block_bucket, 1);
+        let errors = JobSpec::from_json(r#"{"from_block": 1, "to_block": 2, "kind": "aggregate"}"#).unwrap_err();
+        assert_eq!(errors, vec![FieldError::new("aggregate", "required by aggregate job")]);
+        let errors = JobSpec::from_json(
+            r#"{"from_block": 1, "to_block": 2, "kind": "aggregate", "aggregate": {"group_by": ["topic0", "topic0"], "block_bucket": 0}}"#,
+        )
+        .unwrap_err();
+        assert_eq!(
+            errors,
+            vec![
+                FieldError::new("aggregate.block_bucket", "must be greater than 0"),
+                FieldError::new("aggregate.group_by[1]", "duplicate group-by")
+            ]
+        );
+        let errors = JobSpec::from_json(r#"{"from_block": 1, "to_block": 2, "aggregate": {}}"#).unwrap_err();
+        assert_eq!(errors, vec![FieldError::new("aggregate", "not supported by logs job")]);
+
+        let errors = JobSpec::from_json(
+            r#"{"from_block": 1, "to_block": 2, "kind": "receipts", "filter": {"events": ["ERC20.Transfer"]}}"#,
+        )
+        .unwrap_err();
+        assert_eq!(errors, vec![FieldError::new("filter", "not supported by receipts job")]);
+    }
+
+    #[test]
+    fn filter_matches() {
+        let log = KvLog {
+            address: Address::from_low_u64_be(1),
+            topics: vec![H256::from_low_u64_be(10), H256::from_low_u64_be(20)],
+            data: None,
+        };
+        assert!(LogFilter::default().matcher().matches(&log));
+        let filter = LogFilter {
+            addresses: vec![Address::from_low_u64_be(1)],
+            topics: vec![vec![], vec![H256::from_low_u64_be(20), H256::from_low_u64_be(21)]],
+            ..Default::default()
+        };
+        assert!(filter.matcher().matches(&log));
+        let filter = LogFilter {
+            addresses: vec![],
+            topics: vec![vec![], vec![], vec![H256::from_low_u64_be(30)]],
+            ..Default::default()
+        };
+        assert!(!filter.matcher().matches(&log));
+        let filter = LogFilter {
+            addresses: vec![Address::from_low_u64_be(2)],
+            topics: vec![],
+            ..Default::default()
+        };
+        assert!(!filter.matcher().matches(&log));
+
+        let transfer = KvLog {
+            address: Address::from_low_u64_be(1),
+            topics: vec![registry().get("ERC20.Transfer").unwrap().topic0, H256::zero(), H256::zero()],
+            data: None,
+        };
+        let filter = LogFilter {
+            events: vec!["ERC721.Transfer".to_string(), "ERC20.Transfer".to_string()],
+            ..Default::default()
+        };
+        assert!(filter.matcher().matches(&transfer));
+        assert!(!filter.matcher().matches(&log));
+        let filter = LogFilter {
+            events: vec!["ERC721.Transfer".to_string()],
+            ..Default::default()
+        };
+        assert!(!filter.matcher().matches(&transfer));
+    }
+}
@@ -0,0 +1,56 @@
+use crate::job::JobInfo;
+use anyhow::Result;
+use libmdbx::{DatabaseFlags, Environment, Geometry, NoWriteMap, WriteFlags};
+use log::info;
+use std::fs;
+use std::path::Path;
+
+const JOBS_TABLE: &str = "Jobs";
+const MAX_STORE_SIZE: usize = 1 << 30;
+const GROWTH_STEP: isize = 1 << 20;
+
+// Sidecar MDBX with job metadata: job_id -> CBOR JobInfo
+pub struct JobStore {
+    env: Environment<NoWriteMap>,
+}
+
+impl JobStore {
+    pub fn open(path: &Path) -> Result<Self> {
+        info!("Job store:{}", path.to_str().unwrap());
+        fs::create_dir_all(path)?;
+        let env = Environment::new()
+            .set_max_dbs(1)
+            .set_geometry(Geometry {
+                size: Some(0..MAX_STORE_SIZE),
+                growth_step: Some(GROWTH_STEP),
+                shrink_threshold: None,
+                page_size: None,
+            })
+            .open(path)?;
+        let txn = env.begin_rw_txn()?;
+        txn.create_db(Some(JOBS_TABLE), DatabaseFlags::default())?;
+        txn.commit()?;
+        Ok(JobStore { env })
+    }
+
+    pub fn put(&self, job: &JobInfo) -> Result<()> {
+        let value = serde_cbor::to_vec(job)?;
+        let txn = self.env.begin_rw_txn()?;
+        let db = txn.open_db(Some(JOBS_TABLE))?;
+        txn.put(&db, job.job_id.as_bytes(), value, WriteFlags::UPSERT)?;
+        txn.commit()?;
+        Ok(())
+    }
+
+    pub fn load_all(&self) -> Result<Vec<JobInfo>> {
+        let txn = self.env.begin_ro_txn()?;
+        let db = txn.open_db(Some(JOBS_TABLE))?;
+        let mut cursor = txn.cursor(&db)?;
+        let mut jobs = Vec::new();
+        for item in cursor.iter_start::<Vec<u8>, Vec<u8>>() {
+            let (_, value) = item?;
+            jobs.push(serde_cbor::from_slice(&value)?);
+        }
+        Ok(jobs)
+    }
+}
@@ -1,8 +1,14 @@
+mod db;
 mod engine;
 mod storage;
 mod types;
 mod util;
 mod http;
+mod job;
+mod retention;
+mod webhook;
+mod metrics;
+mod decode;
 
 use std::path::PathBuf;
 
@@ -14,22 +20,58 @@ use clap::Parser;
 use log::info;
 use std::sync::Arc;
 use crate::http::HttpApi;
+use crate::job::store::JobStore;
+use crate::job::{JobRegistry, JOB_STORE_DIR};
+use crate::retention::{RetentionPolicy, RetentionSweeper};
+use crate::webhook::WebhookNotifier;
+use crate::metrics::Metrics;
+use std::time::Duration;
 
 #[tokio::main(flavor = "multi_thread")]
 async fn main() {
     setup_log();
     info!("Start app");
     let app_cfg = AppCfg::parse();
-    let result_storage = Arc::new(ResultStorage::new(PathBuf::from(app_cfg.result_path)));
+    let result_path = PathBuf::from(app_cfg.result_path);
+    let metrics = Arc::new(Metrics::new());
+    let result_storage = Arc::new(ResultStorage::new(result_path.clone(), metrics.clone()));
+    let job_store = JobStore::open(&result_path.join(JOB_STORE_DIR)).expect("Err open job store");
+    let job_registry = Arc::new(
+        JobRegistry::open(result_storage.clone(), job_store).expect("Err load job registry"),
+    );
+    let webhook = Arc::new(WebhookNotifier::new(
+        app_cfg.webhook_secret,
+        app_cfg.webhook_max_retries,
+        Duration::from_secs(app_cfg.webhook_timeout_secs),
+    ));
     let search_engine = Arc::new(SearchEngine::new(
         PathBuf::from(app_cfg.db_path),
         result_storage.clone(),
+        job_registry.clone(),
+        app_cfg.max_concurrent_jobs,
+        webhook,
+        metrics.clone(),
+        Duration::from_secs(app_cfg.follow_poll_interval_secs),
     ));
+    search_engine.resume_jobs();
+    search_engine.start_dispatcher();
+
+    let retention_policy = RetentionPolicy {
+        max_age: app_cfg.retention_max_age_secs.map(Duration::from_secs),
+        max_total_size: app_cfg.retention_max_size_bytes,
+    };
+    if retention_policy.is_enabled() {
+        RetentionSweeper::new(retention_policy, result_storage.clone(), job_registry.clone())
+            .spawn(Duration::from_secs(app_cfg.retention_sweep_interval_secs));
+    }
 
-    HttpApi::new(
+    HttpApi::start(
         app_cfg.http_port,
         app_cfg.http_address,
         search_engine.clone(),
+        result_storage.clone(),
+        job_registry.clone(),
+        metrics.clone(),
     )
         .await;
 }
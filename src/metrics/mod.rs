@@ -0,0 +1,185 @@
+use crate::job::JobInfo;
+use anyhow::Result;
+use prometheus::{
+    exponential_buckets, Encoder, Gauge, Histogram, HistogramOpts, IntCounter, IntCounterVec, IntGaugeVec, Opts,
+    Registry, TextEncoder,
+};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::Instant;
+
+const NAMESPACE: &str = "erigon_db_reader";
+const JOB_STATES: [&str; 4] = ["queued", "running", "completed", "failed"];
+
+// Prometheus metrics served by GET /metrics
+pub struct Metrics {
+    registry: Registry,
+    jobs: IntGaugeVec,
+    // logs decoded from TransactionLog
+    pub rows_scanned: IntCounter,
+    // logs matched filter and sent to result writer
+    pub rows_emitted: IntCounter,
+    // size of published result files
+    pub bytes_written: IntCounter,
+    pub cbor_decode_seconds: Histogram,
+    pub chunk_write_seconds: Histogram,
+    read_txn_age: Gauge,
+    // open MDBX read txns by guard id, job may hold several
+    read_txns: Mutex<HashMap<u64, Instant>>,
+    next_txn_id: AtomicU64,
+    http_requests: IntCounterVec,
+}
+
+// Read txn tracked while guard alive
+pub struct ReadTxnGuard<'a> {
+    metrics: &'a Metrics,
+    txn_id: u64,
+}
+
+impl Drop for ReadTxnGuard<'_> {
+    fn drop(&mut self) {
+        self.metrics.read_txns.lock().unwrap().remove(&self.txn_id);
+    }
+}
+
+impl Default for Metrics {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Metrics {
+    pub fn new() -> Self {
+        let registry = Registry::new_custom(Some(NAMESPACE.to_string()), None).unwrap();
+        let jobs = IntGaugeVec::new(Opts::new("jobs", "Known jobs by state"), &["state"]).unwrap();
+        let rows_scanned = IntCounter::new("rows_scanned_total", "Logs read from db").unwrap();
+        let rows_emitted = IntCounter::new("rows_emitted_total", "Logs written to results").unwrap();
+        let bytes_written = IntCounter::new("result_bytes_written_total", "Bytes of published result files").unwrap();
+        let cbor_decode_seconds = Histogram::with_opts(
+            HistogramOpts::new("cbor_decode_seconds", "Decode time of TransactionLog value")
+                .buckets(exponential_buckets(0.000_001, 4.0, 10).unwrap()),
+        )
+        .unwrap();
+        let chunk_write_seconds = Histogram::with_opts(
+            HistogramOpts::new("chunk_write_seconds", "Parquet row group write time")
+                .buckets(exponential_buckets(0.01, 2.0, 12).unwrap()),
+        )
+        .unwrap();
+        let read_txn_age = Gauge::new(
+            "mdbx_read_txn_age_seconds",
+            "Age of oldest open MDBX read txn, 0 when none",
+        )
+        .unwrap();
+        let http_requests = IntCounterVec::new(
+            Opts::new("http_requests_total", "HTTP requests by route and status"),
+            &["method", "route", "status"],
+        )
+        .unwrap();
+
+        registry.register(Box::new(jobs.clone())).unwrap();
+        registry.register(Box::new(rows_scanned.clone())).unwrap();
+        registry.register(Box::new(rows_emitted.clone())).unwrap();
+        registry.register(Box::new(bytes_written.clone())).unwrap();
+        registry.register(Box::new(cbor_decode_seconds.clone())).unwrap();
+        registry.register(Box::new(chunk_write_seconds.clone())).unwrap();
+        registry.register(Box::new(read_txn_age.clone())).unwrap();
+        registry.register(Box::new(http_requests.clone())).unwrap();
+
+        Metrics {
+            registry,
+            jobs,
+            rows_scanned,
+            rows_emitted,
+            bytes_written,
+            cbor_decode_seconds,
+            chunk_write_seconds,
+            read_txn_age,
+            read_txns: Mutex::new(HashMap::new()),
+            next_txn_id: AtomicU64::new(0),
+            http_requests,
+        }
+    }
+
+    pub fn read_txn_opened(&self) -> ReadTxnGuard<'_> {
+        let txn_id = self.next_txn_id.fetch_add(1, Ordering::Relaxed);
+        self.read_txns.lock().unwrap().insert(txn_id, Instant::now());
+        ReadTxnGuard { metrics: self, txn_id }
+    }
+
+    pub fn observe_http(&self, method: &str, route: &str, status: u16) {
+        self.http_requests
+            .with_label_values(&[method, route, &status.to_string()])
+            .inc();
+    }
+
+    // Prometheus text format, job states and txn age taken at scrape time
+    pub fn render(&self, jobs: &[JobInfo]) -> Result<String> {
+        for state in JOB_STATES {
+            let count = jobs.iter().filter(|job| job.status.name() == state).count();
+            self.jobs.with_label_values(&[state]).set(count as i64);
+        }
+        let oldest = self.read_txns.lock().unwrap().values().min().copied();
+        self.read_txn_age
+            .set(oldest.map(|opened| opened.elapsed().as_secs_f64()).unwrap_or_default());
+
+        let mut buf = Vec::new();
+        TextEncoder::new().encode(&self.registry.gather(), &mut buf)?;
+        Ok(String::from_utf8(buf)?)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::job::spec::{Finality, JobSpec};
+    use crate::job::{JobInfo, JobStatus};
+    use crate::metrics::Metrics;
+    use std::path::PathBuf;
+
+    fn job(job_id: &str, status: JobStatus) -> JobInfo {
+        JobInfo {
+            job_id: job_id.to_string(),
+            spec: JobSpec::default(),
+            to_block: 0,
+            finality: Finality::Unfinalized,
+            result_file: PathBuf::from(job_id),
+            status,
+            created_at: 0,
+            started_at: None,
+            finished_at: None,
+            rows: None,
+            last_block: None,
+            parts: 0,
+            block_hashes: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn render() {
+        let metrics = Metrics::new();
+        metrics.rows_scanned.inc_by(10);
+        metrics.rows_emitted.inc_by(3);
+        metrics.observe_http("GET", "/api/v1/jobs", 200);
+        let jobs = vec![
+            job("a", JobStatus::Queued),
+            job("b", JobStatus::Completed),
+            job("c", JobStatus::Completed),
+        ];
+        {
+            let _txn = metrics.read_txn_opened();
+            // second txn of same job closed first
+            drop(metrics.read_txn_opened());
+            let text = metrics.render(&jobs).unwrap();
+            assert!(text.contains("erigon_db_reader_jobs{state=\"completed\"} 2"));
+            assert!(text.contains("erigon_db_reader_jobs{state=\"running\"} 0"));
+            assert!(text.contains("erigon_db_reader_rows_scanned_total 10"));
+            assert!(text.contains("erigon_db_reader_rows_emitted_total 3"));
+            assert!(text.contains(
+                "erigon_db_reader_http_requests_total{method=\"GET\",route=\"/api/v1/jobs\",status=\"200\"} 1"
+            ));
+            assert!(!text.contains("erigon_db_reader_mdbx_read_txn_age_seconds 0\n"));
+        }
+        let text = metrics.render(&jobs).unwrap();
+        assert!(text.contains("erigon_db_reader_mdbx_read_txn_age_seconds 0\n"));
+    }
+}
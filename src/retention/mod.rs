@@ -0,0 +1,184 @@
+use crate::job::{JobRegistry, JobStatus};
+use crate::storage::{ResultFile, ResultStorage};
+use anyhow::Result;
+use log::{error, info};
+use std::sync::Arc;
+use std::time::{Duration, SystemTime};
+use tokio::task::JoinHandle;
+use tokio::time::interval;
+
+#[derive(Debug, Clone, Default)]
+pub struct RetentionPolicy {
+    pub max_age: Option<Duration>,
+    pub max_total_size: Option<u64>,
+}
+
+impl RetentionPolicy {
+    pub fn is_enabled(&self) -> bool {
+        self.max_age.is_some() || self.max_total_size.is_some()
+    }
+
+    // Select results to delete. Input sorted oldest first.
+    pub fn select_expired<'a>(&self, results: &'a [ResultFile], now: SystemTime) -> Vec<&'a ResultFile> {
+        let mut expired = Vec::new();
+        let mut total_size: u64 = results.iter().map(|r| r.size).sum();
+        for result in results {
+            let too_old = self.max_age.is_some_and(|max_age| {
+                now.duration_since(result.modified).unwrap_or_default() > max_age
+            });
+            let too_big = self.max_total_size.is_some_and(|max_size| total_size > max_size);
+            if !too_old && !too_big {
+                continue;
+            }
+            total_size -= result.size;
+            expired.push(result);
+        }
+        expired
+    }
+}
+
+pub struct RetentionSweeper {
+    policy: RetentionPolicy,
+    result_storage: Arc<ResultStorage>,
+    job_registry: Arc<JobRegistry>,
+}
+
+impl RetentionSweeper {
+    pub fn new(policy: RetentionPolicy, result_storage: Arc<ResultStorage>, job_registry: Arc<JobRegistry>) -> Self {
+        info!("Retention policy:{:?}", policy);
+        RetentionSweeper {
+            policy,
+            result_storage,
+            job_registry,
+        }
+    }
+
+    // Delete expired results, return removed job ids
+    pub fn sweep(&self) -> Result<Vec<String>> {
+        let mut results = self.result_storage.list_results()?;
+        // follow job keeps writing parts while running
+        results.retain(|result| {
+            !self
+                .job_registry
+                .get(&result.job_id)
+                .is_some_and(|job| matches!(job.status, JobStatus::Queued | JobStatus::Running))
+        });
+        let mut removed = Vec::new();
+        for result in self.policy.select_expired(&results, SystemTime::now()) {
+            match self.result_storage.delete_result(&result.job_id) {
+                Ok(true) => removed.push(result.job_id.clone()),
+                Ok(false) => {}
+                Err(err) => error!("[{}] Retention delete err:{}", result.job_id, err),
+            }
+        }
+        Ok(removed)
+    }
+
+    pub fn spawn(self, period: Duration) -> JoinHandle<()> {
+        tokio::spawn(async move {
+            let mut ticker = interval(period);
+            loop {
+                ticker.tick().await;
+                match self.sweep() {
+                    Ok(removed) if !removed.is_empty() => {
+                        info!("Retention removed {} results: {:?}", removed.len(), removed)
+                    }
+                    Ok(_) => {}
+                    Err(err) => error!("Retention sweep err:{}", err),
+                }
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::job::spec::{Finality, JobSpec};
+    use crate::job::store::JobStore;
+    use crate::job::{JobRegistry, JOB_STORE_DIR};
+    use crate::metrics::Metrics;
+    use crate::retention::{RetentionPolicy, RetentionSweeper};
+    use crate::storage::events::EventFiles;
+    use crate::storage::{ResultFile, ResultStorage};
+    use std::fs;
+    use std::sync::Arc;
+    use std::time::{Duration, SystemTime};
+
+    fn result_file(job_id: &str, size: u64, age_secs: u64, now: SystemTime) -> ResultFile {
+        ResultFile {
+            job_id: job_id.to_string(),
+            size,
+            modified: now - Duration::from_secs(age_secs),
+        }
+    }
+
+    #[test]
+    fn select_expired() {
+        let now = SystemTime::now();
+        let results = vec![
+            result_file("a", 100, 300, now),
+            result_file("b", 100, 200, now),
+            result_file("c", 100, 100, now),
+            result_file("d", 100, 10, now),
+        ];
+
+        let by_age = RetentionPolicy {
+            max_age: Some(Duration::from_secs(150)),
+            max_total_size: None,
+        };
+        let ids: Vec<&str> = by_age.select_expired(&results, now).iter().map(|r| r.job_id.as_str()).collect();
+        assert_eq!(ids, vec!["a", "b"]);
+
+        let by_size = RetentionPolicy {
+            max_age: None,
+            max_total_size: Some(250),
+        };
+        let ids: Vec<&str> = by_size.select_expired(&results, now).iter().map(|r| r.job_id.as_str()).collect();
+        assert_eq!(ids, vec!["a", "b"]);
+
+        assert!(RetentionPolicy::default().select_expired(&results, now).is_empty());
+    }
+
+    #[test]
+    fn sweep_job_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let result_path = dir.path();
+        let storage = Arc::new(ResultStorage::new(result_path.to_path_buf(), Arc::new(Metrics::new())));
+        let store = JobStore::open(&result_path.join(JOB_STORE_DIR)).unwrap();
+        let registry = Arc::new(JobRegistry::open(storage.clone(), store).unwrap());
+        // running follow job between parts
+        registry
+            .register(JobSpec {
+                job_id: Some("follow".to_string()),
+                follow: true,
+                ..Default::default()
+            }, 0, Finality::Unfinalized)
+            .unwrap();
+        registry.start("follow");
+        fs::write(storage.part_file("follow", 0), [0; 10]).unwrap();
+        fs::write(storage.part_file("follow", 1), [0; 10]).unwrap();
+        fs::write(storage.retraction_file("follow", 2), [0; 10]).unwrap();
+        // stopped follow job and decoded job
+        fs::write(storage.part_file("old", 0), [0; 10]).unwrap();
+        fs::write(storage.part_file("old", 1), [0; 10]).unwrap();
+        fs::write(storage.result_file("decoded"), [0; 10]).unwrap();
+        fs::write(EventFiles::event_file(&storage.result_file("decoded"), "Transfer"), [0; 5]).unwrap();
+
+        let mut results = storage.list_results().unwrap();
+        results.sort_by(|a, b| a.job_id.cmp(&b.job_id));
+        let sizes: Vec<(&str, u64)> = results.iter().map(|r| (r.job_id.as_str(), r.size)).collect();
+        assert_eq!(sizes, vec![("decoded", 15), ("follow", 30), ("old", 20)]);
+
+        let policy = RetentionPolicy {
+            max_age: None,
+            max_total_size: Some(0),
+        };
+        let mut removed = RetentionSweeper::new(policy, storage.clone(), registry).sweep().unwrap();
+        removed.sort();
+        assert_eq!(removed, vec!["decoded", "old"]);
+        assert!(storage.part_file("follow", 0).exists());
+        assert!(storage.retraction_file("follow", 2).exists());
+        assert!(!storage.part_file("old", 1).exists());
+        assert!(!EventFiles::event_file(&storage.result_file("decoded"), "Transfer").exists());
+    }
+}
@@ -0,0 +1,142 @@
+use crate::decode::{ColumnType, DecodedLog, EventDecoder};
+use crate::metrics::Metrics;
+use crate::storage::table::{ColumnData, TableWriter};
+use crate::storage::RESULT_FILE_EXT;
+use crate::types::KvLog;
+use anyhow::Result;
+use ethers::abi::Token;
+use ethers::types::Address;
+use parquet::data_type::ByteArray;
+use parquet::file::properties::WriterProperties;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+// Decoded log buffered until end of chunk
+struct EventRow {
+    block_n: u64,
+    tx_n: u32,
+    contract: Address,
+    values: Vec<Token>,
+}
+
+// Decoded logs of job, one file per event next to result file.
+// File created on first decoded log, temp files removed unless published
+pub struct EventFiles {
+    decoder: Arc<EventDecoder>,
+    job_id: String,
+    result_file: PathBuf,
+    props: WriterProperties,
+    metrics: Arc<Metrics>,
+    writers: Vec<Option<TableWriter>>,
+    rows: Vec<Vec<EventRow>>,
+}
+
+impl EventFiles {
+    pub fn new(
+        decoder: Arc<EventDecoder>,
+        job_id: &str,
+        result_file: &Path,
+        props: WriterProperties,
+        metrics: Arc<Metrics>,
+    ) -> Self {
+        let events = decoder.events().len();
+        EventFiles {
+            decoder,
+            job_id: job_id.to_string(),
+            result_file: result_file.to_path_buf(),
+            props,
+            metrics,
+            writers: (0..events).map(|_| None).collect(),
+            rows: (0..events).map(|_| Vec::new()).collect(),
+        }
+    }
+
+    // <job_id>.<event>.parquet, part: <job_id>.part-000001.<event>.parquet
+    pub fn event_file(result_file: &Path, event: &str) -> PathBuf {
+        result_file.with_extension(format!("{}.{}", event, RESULT_FILE_EXT))
+    }
+
+    // Decode logs of chunk, write row group per event. Return decoded logs count
+    pub fn write(&mut self, txs: &[(u64, u32, KvLog)]) -> Result<u64> {
+        let mut decoded = 0;
+        for (block_n, tx_n, log) in txs {
+            if let Some(DecodedLog { event, values }) = self.decoder.decode(log) {
+                self.rows[event].push(EventRow {
+                    block_n: *block_n,
+                    tx_n: *tx_n,
+                    contract: log.address,
+                    values,
+                });
+                decoded += 1;
+            }
+        }
+        for event in 0..self.rows.len() {
+            if !self.rows[event].is_empty() {
+                self.flush(event)?;
+            }
+        }
+        Ok(decoded)
+    }
+
+    fn flush(&mut self, event: usize) -> Result<()> {
+        let rows = std::mem::take(&mut self.rows[event]);
+        let schema = &self.decoder.events()[event];
+        if self.writers[event].is_none() {
+            // job marked active by log writer
+            self.writers[event] = Some(TableWriter::create(
+                &self.job_id,
+                &Self::event_file(&self.result_file, &schema.name),
+                &schema.message_type(),
+                self.props.clone(),
+                None,
+                self.metrics.clone(),
+            )?);
+        }
+        let mut columns = vec![
+            ColumnData::Int64(rows.iter().map(|r| r.block_n as i64).collect()),
+            ColumnData::Int32(rows.iter().map(|r| r.tx_n as i32).collect()),
+            ColumnData::Bytes(rows.iter().map(|r| ByteArray::from(r.contract.as_bytes())).collect()),
+        ];
+        for (i, (_, column_type)) in schema.columns.iter().enumerate() {
+            let values = rows.iter().map(|r| &r.values[i]);
+            columns.push(match column_type {
+                ColumnType::Int64 => ColumnData::Int64(values.map(int64_value).collect()),
+                ColumnType::Bool => ColumnData::Bool(values.map(|v| matches!(v, Token::Bool(true))).collect()),
+                ColumnType::Binary => ColumnData::Bytes(values.map(|v| ByteArray::from(binary_value(v))).collect()),
+                ColumnType::Utf8 => {
+                    ColumnData::Bytes(values.map(|v| ByteArray::from(v.to_string().into_bytes())).collect())
+                }
+            });
+        }
+        self.writers[event].as_mut().unwrap().write_row_group(columns)
+    }
+
+    // Rename event files to final names, unpublished temp files removed on drop
+    pub fn publish(self) -> Result<()> {
+        for writer in self.writers.into_iter().flatten() {
+            writer.publish()?;
+        }
+        Ok(())
+    }
+}
+
+// Low 64 bits, int <= 64 bits two's complement
+fn int64_value(token: &Token) -> i64 {
+    match token {
+        Token::Uint(v) | Token::Int(v) => v.low_u64() as i64,
+        _ => 0,
+    }
+}
+
+fn binary_value(token: &Token) -> Vec<u8> {
+    match token {
+        Token::Address(address) => address.as_bytes().to_vec(),
+        Token::Bytes(bytes) | Token::FixedBytes(bytes) => bytes.clone(),
+        Token::Uint(v) | Token::Int(v) => {
+            let mut buf = [0u8; 32];
+            v.to_big_endian(&mut buf);
+            buf.to_vec()
+        }
+        token => token.to_string().into_bytes(),
+    }
+}
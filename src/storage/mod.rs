@@ -1,21 +1,34 @@
+pub mod events;
+pub mod table;
+
+use crate::decode::registry::registry;
+use crate::decode::EventDecoder;
 use crate::engine::LOG_CHUNK_SIZE;
+use crate::job::spec::{Finality, JobSpec, OutputCompression};
+use crate::storage::events::EventFiles;
+use crate::storage::table::{ColumnData, TableWriter};
+use crate::metrics::Metrics;
 use crate::types::LogChunk;
+use anyhow::{bail, Result};
 use bytes::Bytes;
 use ethers::types::H256;
-use log::info;
-use parquet::basic::{Compression, Encoding};
+use log::{error, info, warn};
+use parquet::basic::Encoding;
 use parquet::data_type::{ByteArray, ByteArrayType, Int32Type, Int64Type};
+use parquet::file::metadata::KeyValue;
 use parquet::file::properties::{WriterProperties, WriterVersion};
 use parquet::file::writer::SerializedFileWriter;
 use parquet::schema::parser::parse_message_type;
+use std::collections::{HashMap, HashSet};
 use std::fs;
 use std::path::{Path, PathBuf};
-use std::sync::Arc;
-use std::time::Instant;
+use std::sync::{Arc, Mutex};
+use std::time::{Instant, SystemTime};
 use tokio::spawn;
 use tokio::sync::mpsc;
-use tokio::sync::mpsc::Sender;
+use tokio::sync::mpsc::{Receiver, Sender};
 use tokio::task::JoinHandle;
+use ulid::Ulid;
 
 /*
 Tables arch: Erigon accessors_chain.go
@@ -30,6 +43,43 @@ mdbx_chk version 0.12.0.71
  */
 pub struct ResultStorage {
     result_path: PathBuf,
+    // job ids with a writer still running
+    active: Arc<Mutex<HashSet<String>>>,
+    metrics: Arc<Metrics>,
+}
+
+pub const RESULT_FILE_EXT: &str = "parquet";
+const TMP_FILE_EXT: &str = "tmp";
+const PART_PREFIX: &str = "part-";
+// Parquet key-value metadata: finality of exported blocks
+pub const FINALITY_METADATA_KEY: &str = "finality";
+const RETRACT_SUFFIX: &str = "-retract";
+
+// Output of job: compression, finality metadata and decoded event files
+#[derive(Clone, Default)]
+pub struct WriterOptions {
+    pub compression: OutputCompression,
+    pub finality: Finality,
+    pub decoder: Option<Arc<EventDecoder>>,
+}
+
+impl WriterOptions {
+    pub fn new(spec: &JobSpec, finality: Finality) -> Result<Self> {
+        let decoder = spec.decode.as_ref().map(EventDecoder::new).transpose()?;
+        Ok(WriterOptions {
+            compression: spec.output.compression,
+            finality,
+            decoder: decoder.map(Arc::new),
+        })
+    }
+}
+
+// Completed result file
+#[derive(Debug, Clone)]
+pub struct ResultFile {
+    pub job_id: String,
+    pub size: u64,
+    pub modified: SystemTime,
 }
 
 const LOGS_MESSAGE_TYPE: &str = "
@@ -43,220 +93,508 @@ const LOGS_MESSAGE_TYPE: &str = "
                 OPTIONAL BYTE_ARRAY topic2;
                 OPTIONAL BYTE_ARRAY topic3;
                 OPTIONAL BYTE_ARRAY data;
+                OPTIONAL BYTE_ARRAY event_name (UTF8);
+                }
+            ";
+
+// Blocks of earlier parts unwound by reorg, consumers drop their logs
+const RETRACT_MESSAGE_TYPE: &str = "
+              message schema {
+                REQUIRED INT64 block_n;
+                REQUIRED BYTE_ARRAY block_hash;
                 }
             ";
 
 impl ResultStorage {
-    pub fn new(result_path: PathBuf) -> Self {
+    pub fn new(result_path: PathBuf, metrics: Arc<Metrics>) -> Self {
         info!("Result storage:{}", result_path.to_str().unwrap());
-        ResultStorage { result_path }
+        Self::remove_stale_tmp_files(&result_path);
+        ResultStorage {
+            result_path,
+            active: Arc::new(Mutex::new(HashSet::new())),
+            metrics,
+        }
+    }
+
+    // Temp files left by a crashed writer never become results
+    fn remove_stale_tmp_files(result_path: &Path) {
+        let entries = match fs::read_dir(result_path) {
+            Ok(entries) => entries,
+            Err(err) => {
+                warn!("Read result path err:{}", err);
+                return;
+            }
+        };
+        for entry in entries.flatten() {
+            let path = entry.path();
+            let is_tmp = path.extension().and_then(|e| e.to_str()) == Some(TMP_FILE_EXT)
+                && path
+                    .file_stem()
+                    .and_then(|s| Path::new(s).extension())
+                    .and_then(|e| e.to_str())
+                    == Some(RESULT_FILE_EXT);
+            if is_tmp {
+                info!("Remove stale temp file:{}", path.to_str().unwrap());
+                if let Err(err) = fs::remove_file(&path) {
+                    warn!("Remove stale temp file err:{}", err);
+                }
+            }
+        }
+    }
+
+    pub fn result_file(&self, job_id: &str) -> PathBuf {
+        self.result_path.join(format!("{}.{}", job_id, RESULT_FILE_EXT))
+    }
+
+    // Part file of follow job: <job_id>.part-000001.parquet
+    pub fn part_file(&self, job_id: &str, part: u32) -> PathBuf {
+        self.result_path
+            .join(format!("{}.{}{:06}.{}", job_id, PART_PREFIX, part, RESULT_FILE_EXT))
+    }
+
+    // Prefix of follow job part files next to result file: <job_id>.part-
+    pub fn part_prefix(result_file: &Path) -> PathBuf {
+        result_file.with_extension(PART_PREFIX)
+    }
+
+    // Remove published part without rows
+    pub fn discard_part(&self, job_id: &str, part: u32) -> Result<()> {
+        fs::remove_file(self.part_file(job_id, part))?;
+        Ok(())
+    }
+
+    pub fn retraction_file(&self, job_id: &str, part: u32) -> PathBuf {
+        self.result_path.join(format!(
+            "{}.{}{:06}{}.{}",
+            job_id, PART_PREFIX, part, RETRACT_SUFFIX, RESULT_FILE_EXT
+        ))
+    }
+
+    // Write retracted blocks with their orphaned hashes as part
+    pub fn write_retraction(&self, job_id: &str, part: u32, blocks: &[(u64, H256)]) -> Result<()> {
+        let retraction_file = self.retraction_file(job_id, part);
+        let props = WriterProperties::builder()
+            .set_writer_version(WriterVersion::PARQUET_2_0)
+            .build();
+        let mut writer = TableWriter::create(
+            job_id,
+            &retraction_file,
+            RETRACT_MESSAGE_TYPE,
+            props,
+            Some(self.active.clone()),
+            self.metrics.clone(),
+        )?;
+        writer.write_row_group(vec![
+            ColumnData::Int64(blocks.iter().map(|(n, _)| *n as i64).collect()),
+            ColumnData::Bytes(blocks.iter().map(|(_, h)| ByteArray::from(h.as_bytes())).collect()),
+        ])?;
+        writer.publish()?;
+        info!(
+            "[{}] Retraction written:{}",
+            job_id,
+            retraction_file.to_str().unwrap()
+        );
+        Ok(())
+    }
+
+    // Part, retraction and event files: <job_id>.<name>.parquet, job id has no dots
+    fn job_files(&self, job_id: &str) -> Result<Vec<PathBuf>> {
+        let prefix = format!("{}.", job_id);
+        let result_name = format!("{}{}", prefix, RESULT_FILE_EXT);
+        let mut files = Vec::new();
+        for entry in fs::read_dir(&self.result_path)? {
+            let path = entry?.path();
+            let is_job_file = path.extension().and_then(|e| e.to_str()) == Some(RESULT_FILE_EXT)
+                && path
+                    .file_name()
+                    .and_then(|n| n.to_str())
+                    .is_some_and(|n| n.starts_with(&prefix) && n != result_name);
+            if is_job_file {
+                files.push(path);
+            }
+        }
+        Ok(files)
+    }
+
+    // Compression and finality metadata of job output
+    fn writer_props(options: &WriterOptions) -> WriterProperties {
+        WriterProperties::builder()
+            .set_writer_version(WriterVersion::PARQUET_2_0)
+            .set_encoding(Encoding::PLAIN)
+            .set_compression(options.compression.to_parquet())
+            .set_key_value_metadata(Some(vec![KeyValue::new(
+                FINALITY_METADATA_KEY.to_string(),
+                options.finality.name().to_string(),
+            )]))
+            .build()
+    }
+
+    // Writer of summary table jobs
+    pub fn create_table(
+        &self,
+        job_id: &str,
+        result_file: &Path,
+        message_type: &str,
+        options: &WriterOptions,
+    ) -> Result<TableWriter> {
+        info!(
+            "[{}] Start table writer. Result file:{}",
+            job_id,
+            result_file.to_str().unwrap()
+        );
+        TableWriter::create(
+            job_id,
+            result_file,
+            message_type,
+            Self::writer_props(options),
+            Some(self.active.clone()),
+            self.metrics.clone(),
+        )
+    }
+
+    // Result is written here and renamed to result file on success
+    fn tmp_file(result_file: &Path) -> PathBuf {
+        result_file.with_extension(format!("{}.{}", RESULT_FILE_EXT, TMP_FILE_EXT))
+    }
+
+    // Create and remove probe file in result path, unique name per check as probes run concurrently
+    pub fn check_writable(&self) -> Result<()> {
+        let probe = self.result_path.join(format!(".write_probe-{}", Ulid::new()));
+        fs::write(&probe, b"ok")?;
+        fs::remove_file(&probe)?;
+        Ok(())
+    }
+
+    pub fn is_active(&self, job_id: &str) -> bool {
+        self.active.lock().unwrap().contains(job_id)
+    }
+
+    // Completed results with their part and event files, oldest first
+    pub fn list_results(&self) -> Result<Vec<ResultFile>> {
+        let mut results: HashMap<String, ResultFile> = HashMap::new();
+        for entry in fs::read_dir(&self.result_path)? {
+            let path = entry?.path();
+            if path.extension().and_then(|e| e.to_str()) != Some(RESULT_FILE_EXT) {
+                continue;
+            }
+            // <job_id>.parquet, <job_id>.part-000001.parquet, <job_id>.<Event>.parquet
+            let job_id = match path.file_name().and_then(|n| n.to_str()).and_then(|n| n.split('.').next()) {
+                Some(job_id) if !job_id.is_empty() => job_id.to_string(),
+                _ => continue,
+            };
+            if self.is_active(&job_id) {
+                continue;
+            }
+            let meta = fs::metadata(&path)?;
+            if !meta.is_file() {
+                continue;
+            }
+            let modified = meta.modified()?;
+            let result = results.entry(job_id.clone()).or_insert(ResultFile {
+                job_id,
+                size: 0,
+                modified,
+            });
+            result.size += meta.len();
+            result.modified = result.modified.max(modified);
+        }
+        let mut results: Vec<ResultFile> = results.into_values().collect();
+        results.sort_by_key(|r| r.modified);
+        Ok(results)
+    }
+
+    // Delete result, part and event files, return false if none exists
+    pub fn delete_result(&self, job_id: &str) -> Result<bool> {
+        if Path::new(job_id).file_name().and_then(|n| n.to_str()) != Some(job_id) {
+            bail!("Invalid job id:{}", job_id);
+        }
+        if self.is_active(job_id) {
+            bail!("Job {} is still writing result", job_id);
+        }
+        let mut files = self.job_files(job_id)?;
+        let result_file = self.result_file(job_id);
+        if result_file.is_file() {
+            files.push(result_file);
+        }
+        for file in &files {
+            fs::remove_file(file)?;
+            info!("[{}] Result deleted:{}", job_id, file.to_str().unwrap());
+        }
+        Ok(!files.is_empty())
     }
+
     // Return mq sender and corutine
-    pub async fn open_writer(&self, job_id: String) -> (Sender<Option<LogChunk>>, JoinHandle<()>) {
+    pub async fn open_writer(
+        &self,
+        job_id: String,
+        result_file: PathBuf,
+        options: WriterOptions,
+    ) -> (Sender<Option<LogChunk>>, JoinHandle<Result<()>>) {
         let (sender, mut rcv) = mpsc::channel::<Option<LogChunk>>(8);
-        let result_file = self.result_path.join(Path::new(&job_id));
+        let tmp_file = Self::tmp_file(&result_file);
         let _job_id = job_id.clone();
+        let active = self.active.clone();
+        let metrics = self.metrics.clone();
+        active.lock().unwrap().insert(job_id.clone());
         info!(
             "[{}] Start result writer. Result file:{}",
             job_id,
             result_file.to_str().unwrap()
         );
-        let worker: JoinHandle<()> = spawn(async move {
-            let schema = Arc::new(parse_message_type(LOGS_MESSAGE_TYPE).unwrap());
-            let file = fs::File::create(&result_file).unwrap();
-            let props = WriterProperties::builder()
-                .set_writer_version(WriterVersion::PARQUET_2_0)
-                .set_encoding(Encoding::PLAIN)
-                .set_compression(Compression::SNAPPY)
-                .build();
-            let mut writer = SerializedFileWriter::new(file, schema, props.into()).unwrap();
-            // reuse
-            let mut block_n_col = Vec::with_capacity(LOG_CHUNK_SIZE);
-            let mut tx_n_col = Vec::with_capacity(LOG_CHUNK_SIZE);
-            let mut ctr_col = Vec::with_capacity(LOG_CHUNK_SIZE);
-            let mut op_code_col = Vec::with_capacity(LOG_CHUNK_SIZE);
-            let mut topic_0_col = Vec::with_capacity(LOG_CHUNK_SIZE);
-            let mut topic_0_def_level_col = Vec::with_capacity(LOG_CHUNK_SIZE);
-            let mut topic_1_col = Vec::with_capacity(LOG_CHUNK_SIZE);
-            let mut topic_1_def_level_col = Vec::with_capacity(LOG_CHUNK_SIZE);
-            let mut topic_2_col = Vec::with_capacity(LOG_CHUNK_SIZE);
-            let mut topic_2_def_level_col = Vec::with_capacity(LOG_CHUNK_SIZE);
-            let mut topic_3_col = Vec::with_capacity(LOG_CHUNK_SIZE);
-            let mut topic_3_def_level_col = Vec::with_capacity(LOG_CHUNK_SIZE);
-            let mut data_col = Vec::with_capacity(LOG_CHUNK_SIZE);
-            let mut data_def_level_col = Vec::with_capacity(LOG_CHUNK_SIZE);
-
-            while let Some(chunk) = rcv.recv().await {
-                if chunk.is_none() {
-                    break;
+        let worker: JoinHandle<Result<()>> = spawn(async move {
+            let write_result = Self::write_logs(&mut rcv, &result_file, &options, &_job_id, &metrics).await;
+            // publish complete file only
+            let publish_result =
+                write_result.and_then(|_| fs::rename(&tmp_file, &result_file).map_err(Into::into));
+            match &publish_result {
+                Ok(_) => {
+                    if let Ok(meta) = fs::metadata(&result_file) {
+                        metrics.bytes_written.inc_by(meta.len());
+                    }
                 }
-                let chunk = chunk.unwrap();
-                info!("[{}] Chunk size: {:?}", _job_id, chunk.txs.len());
-                let now = Instant::now();
-
-                for _log_model in chunk.txs {
-                    let (block_n, tx_n, logs) = _log_model;
-                    block_n_col.push(block_n as i64);
-                    tx_n_col.push(tx_n as i32);
-                    ctr_col.push(ByteArray::from(logs.address.as_bytes()));
-                    op_code_col.push(logs.topics.len() as i32);
-
-                    Self::populate_topic(
-                        &mut topic_0_col,
-                        &mut topic_0_def_level_col,
-                        logs.topics.get(0),
-                    );
-                    Self::populate_topic(
-                        &mut topic_1_col,
-                        &mut topic_1_def_level_col,
-                        logs.topics.get(1),
-                    );
-                    Self::populate_topic(
-                        &mut topic_2_col,
-                        &mut topic_2_def_level_col,
-                        logs.topics.get(2),
-                    );
-                    Self::populate_topic(
-                        &mut topic_3_col,
-                        &mut topic_3_def_level_col,
-                        logs.topics.get(3),
-                    );
-
-                    Self::populate_binary(&mut data_col, &mut data_def_level_col, logs.data);
+                Err(err) => {
+                    error!("[{}] Result writer err:{}", job_id, err);
+                    if let Err(err) = fs::remove_file(&tmp_file) {
+                        error!("[{}] Remove temp file err:{}", job_id, err);
+                    }
                 }
-
-                let mut row_group_writer = writer.next_row_group().unwrap();
-                let mut col_writer = row_group_writer.next_column().unwrap().unwrap();
-                col_writer
-                    .typed::<Int64Type>()
-                    .write_batch(&block_n_col, None, None)
-                    .unwrap();
-                col_writer.close().unwrap();
-
-                let mut col_writer = row_group_writer.next_column().unwrap().unwrap();
-                col_writer
-                    .typed::<Int32Type>()
-                    .write_batch(&tx_n_col, None, None)
-                    .unwrap();
-                col_writer.close().unwrap();
-
-                let mut col_writer = row_group_writer.next_column().unwrap().unwrap();
-                col_writer
-                    .typed::<ByteArrayType>()
-                    .write_batch(&ctr_col, None, None)
-                    .unwrap();
-                col_writer.close().unwrap();
-
-                let mut col_writer = row_group_writer.next_column().unwrap().unwrap();
-                col_writer
-                    .typed::<Int32Type>()
-                    .write_batch(&op_code_col, None, None)
-                    .unwrap();
-                col_writer.close().unwrap();
-
-                let mut col_writer = row_group_writer.next_column().unwrap().unwrap();
-                col_writer
-                    .typed::<ByteArrayType>()
-                    .write_batch(&topic_0_col, Some(&topic_0_def_level_col), None)
-                    .unwrap();
-                col_writer.close().unwrap();
-
-                let mut col_writer = row_group_writer.next_column().unwrap().unwrap();
-                col_writer
-                    .typed::<ByteArrayType>()
-                    .write_batch(&topic_1_col, Some(&topic_1_def_level_col), None)
-                    .unwrap();
-                col_writer.close().unwrap();
-
-                let mut col_writer = row_group_writer.next_column().unwrap().unwrap();
-                col_writer
-                    .typed::<ByteArrayType>()
-                    .write_batch(&topic_2_col, Some(&topic_2_def_level_col), None)
-                    .unwrap();
-                col_writer.close().unwrap();
-
-                let mut col_writer = row_group_writer.next_column().unwrap().unwrap();
-                col_writer
-                    .typed::<ByteArrayType>()
-                    .write_batch(&topic_3_col, Some(&topic_3_def_level_col), None)
-                    .unwrap();
-                col_writer.close().unwrap();
-
-                let mut col_writer = row_group_writer.next_column().unwrap().unwrap();
-                col_writer
-                    .typed::<ByteArrayType>()
-                    .write_batch(&data_col, Some(&data_def_level_col), None)
-                    .unwrap();
-                col_writer.close().unwrap();
-                row_group_writer.close().unwrap();
-
-                // free mem
-                block_n_col.clear();
-                tx_n_col.clear();
-                ctr_col.clear();
-                op_code_col.clear();
-                topic_0_col.clear();
-                topic_0_def_level_col.clear();
-                topic_1_col.clear();
-                topic_1_def_level_col.clear();
-                topic_2_col.clear();
-                topic_2_def_level_col.clear();
-                topic_3_col.clear();
-                topic_3_def_level_col.clear();
-                data_col.clear();
-                data_def_level_col.clear();
-
-                info!("[{}] Chunk write took:{}ms", _job_id, now.elapsed().as_millis());
             }
-
-            info!("[{}] Stop result writer.", job_id);
-            writer.close().unwrap();
+            active.lock().unwrap().remove(&job_id);
+            publish_result
         });
 
         (sender, worker)
     }
 
+    async fn write_logs(
+        rcv: &mut Receiver<Option<LogChunk>>,
+        result_file: &Path,
+        options: &WriterOptions,
+        job_id: &str,
+        metrics: &Arc<Metrics>,
+    ) -> Result<()> {
+        let schema = Arc::new(parse_message_type(LOGS_MESSAGE_TYPE)?);
+        let file = fs::File::create(Self::tmp_file(result_file))?;
+        let props = Self::writer_props(options);
+        let mut event_files = options
+            .decoder
+            .clone()
+            .map(|decoder| EventFiles::new(decoder, job_id, result_file, props.clone(), metrics.clone()));
+        let mut writer = SerializedFileWriter::new(file, schema, props.into())?;
+        // reuse
+        let mut block_n_col = Vec::with_capacity(LOG_CHUNK_SIZE);
+        let mut tx_n_col = Vec::with_capacity(LOG_CHUNK_SIZE);
+        let mut ctr_col = Vec::with_capacity(LOG_CHUNK_SIZE);
+        let mut op_code_col = Vec::with_capacity(LOG_CHUNK_SIZE);
+        let mut topic_0_col = Vec::with_capacity(LOG_CHUNK_SIZE);
+        let mut topic_0_def_level_col = Vec::with_capacity(LOG_CHUNK_SIZE);
+        let mut topic_1_col = Vec::with_capacity(LOG_CHUNK_SIZE);
+        let mut topic_1_def_level_col = Vec::with_capacity(LOG_CHUNK_SIZE);
+        let mut topic_2_col = Vec::with_capacity(LOG_CHUNK_SIZE);
+        let mut topic_2_def_level_col = Vec::with_capacity(LOG_CHUNK_SIZE);
+        let mut topic_3_col = Vec::with_capacity(LOG_CHUNK_SIZE);
+        let mut topic_3_def_level_col = Vec::with_capacity(LOG_CHUNK_SIZE);
+        let mut data_col = Vec::with_capacity(LOG_CHUNK_SIZE);
+        let mut data_def_level_col = Vec::with_capacity(LOG_CHUNK_SIZE);
+        let mut event_name_col = Vec::with_capacity(LOG_CHUNK_SIZE);
+        let mut event_name_def_level_col = Vec::with_capacity(LOG_CHUNK_SIZE);
+
+        loop {
+            let chunk = match rcv.recv().await {
+                Some(Some(chunk)) => chunk,
+                // end of job
+                Some(None) => break,
+                // sender dropped without end of job, result incomplete
+                None => bail!("Result channel closed before end of job"),
+            };
+            info!("[{}] Chunk size: {:?}", job_id, chunk.txs.len());
+            let now = Instant::now();
+            if let Some(event_files) = &mut event_files {
+                let decoded = event_files.write(&chunk.txs)?;
+                info!("[{}] Decoded logs:{}", job_id, decoded);
+            }
+
+            for _log_model in chunk.txs {
+                let (block_n, tx_n, logs) = _log_model;
+                block_n_col.push(block_n as i64);
+                tx_n_col.push(tx_n as i32);
+                ctr_col.push(ByteArray::from(logs.address.as_bytes()));
+                op_code_col.push(logs.topics.len() as i32);
+
+                Self::populate_topic(
+                    &mut topic_0_col,
+                    &mut topic_0_def_level_col,
+                    logs.topics.first(),
+                );
+                Self::populate_topic(
+                    &mut topic_1_col,
+                    &mut topic_1_def_level_col,
+                    logs.topics.get(1),
+                );
+                Self::populate_topic(
+                    &mut topic_2_col,
+                    &mut topic_2_def_level_col,
+                    logs.topics.get(2),
+                );
+                Self::populate_topic(
+                    &mut topic_3_col,
+                    &mut topic_3_def_level_col,
+                    logs.topics.get(3),
+                );
+
+                // known event of topic0
+                let event_name = registry().resolve(&logs).map(|event| event.name);
+                Self::populate_event_name(&mut event_name_col, &mut event_name_def_level_col, event_name);
+                Self::populate_binary(&mut data_col, &mut data_def_level_col, logs.data);
+            }
+
+            let mut row_group_writer = writer.next_row_group()?;
+            let mut col_writer = row_group_writer.next_column()?.unwrap();
+            col_writer
+                .typed::<Int64Type>()
+                .write_batch(&block_n_col, None, None)?;
+            col_writer.close()?;
+
+            let mut col_writer = row_group_writer.next_column()?.unwrap();
+            col_writer
+                .typed::<Int32Type>()
+                .write_batch(&tx_n_col, None, None)?;
+            col_writer.close()?;
+
+            let mut col_writer = row_group_writer.next_column()?.unwrap();
+            col_writer
+                .typed::<ByteArrayType>()
+                .write_batch(&ctr_col, None, None)?;
+            col_writer.close()?;
+
+            let mut col_writer = row_group_writer.next_column()?.unwrap();
+            col_writer
+                .typed::<Int32Type>()
+                .write_batch(&op_code_col, None, None)?;
+            col_writer.close()?;
+
+            let mut col_writer = row_group_writer.next_column()?.unwrap();
+            col_writer
+                .typed::<ByteArrayType>()
+                .write_batch(&topic_0_col, Some(&topic_0_def_level_col), None)?;
+            col_writer.close()?;
+
+            let mut col_writer = row_group_writer.next_column()?.unwrap();
+            col_writer
+                .typed::<ByteArrayType>()
+                .write_batch(&topic_1_col, Some(&topic_1_def_level_col), None)?;
+            col_writer.close()?;
+
+            let mut col_writer = row_group_writer.next_column()?.unwrap();
+            col_writer
+                .typed::<ByteArrayType>()
+                .write_batch(&topic_2_col, Some(&topic_2_def_level_col), None)?;
+            col_writer.close()?;
+
+            let mut col_writer = row_group_writer.next_column()?.unwrap();
+            col_writer
+                .typed::<ByteArrayType>()
+                .write_batch(&topic_3_col, Some(&topic_3_def_level_col), None)?;
+            col_writer.close()?;
+
+            let mut col_writer = row_group_writer.next_column()?.unwrap();
+            col_writer
+                .typed::<ByteArrayType>()
+                .write_batch(&data_col, Some(&data_def_level_col), None)?;
+            col_writer.close()?;
+
+            let mut col_writer = row_group_writer.next_column()?.unwrap();
+            col_writer
+                .typed::<ByteArrayType>()
+                .write_batch(&event_name_col, Some(&event_name_def_level_col), None)?;
+            col_writer.close()?;
+            row_group_writer.close()?;
+
+            // free mem
+            block_n_col.clear();
+            tx_n_col.clear();
+            ctr_col.clear();
+            op_code_col.clear();
+            topic_0_col.clear();
+            topic_0_def_level_col.clear();
+            topic_1_col.clear();
+            topic_1_def_level_col.clear();
+            topic_2_col.clear();
+            topic_2_def_level_col.clear();
+            topic_3_col.clear();
+            topic_3_def_level_col.clear();
+            data_col.clear();
+            data_def_level_col.clear();
+            event_name_col.clear();
+            event_name_def_level_col.clear();
+
+            metrics.chunk_write_seconds.observe(now.elapsed().as_secs_f64());
+            info!("[{}] Chunk write took:{}ms", job_id, now.elapsed().as_millis());
+        }
+
+        info!("[{}] Stop result writer.", job_id);
+        writer.close()?;
+        // published before result file, which marks job output complete
+        if let Some(event_files) = event_files {
+            event_files.publish()?;
+        }
+        Ok(())
+    }
+
     fn populate_topic(col: &mut Vec<ByteArray>, def_level_col: &mut Vec<i16>, topic: Option<&H256>) {
         if let Some(data) = topic {
             col.push(ByteArray::from(data.as_bytes()));
             def_level_col.push(1_i16);
         } else {
-            col.push(ByteArray::from(vec![]));
+            // values only for rows with def level 1
             def_level_col.push(0_i16);
         }
     }
 
+    fn populate_event_name(col: &mut Vec<ByteArray>, def_level_col: &mut Vec<i16>, name: Option<&str>) {
+        if let Some(name) = name {
+            col.push(ByteArray::from(name));
+            def_level_col.push(1_i16);
+        } else {
+            // values only for rows with def level 1
+            def_level_col.push(0_i16);
+        }
+    }
     fn populate_binary(col: &mut Vec<ByteArray>, def_level_col: &mut Vec<i16>, binary: Option<Bytes>) {
-        if let Some(data) = binary {
-            if data.is_empty() {
-                col.push(ByteArray::from(vec![]));
-                def_level_col.push(0_i16);
-            } else {
+        match binary {
+            Some(data) if !data.is_empty() => {
                 col.push(ByteArray::from(data));
                 def_level_col.push(1_i16);
             }
-        } else {
-            col.push(ByteArray::from(vec![]));
-            def_level_col.push(0_i16);
+            // values only for rows with def level 1
+            _ => def_level_col.push(0_i16),
         }
     }
 }
 #[cfg(test)]
 mod test {
+    use crate::decode::EventDecoder;
+    use crate::job::spec::{DecodeSpec, Finality};
+    use crate::metrics::Metrics;
+    use crate::storage::events::EventFiles;
+    use crate::storage::{ResultStorage, WriterOptions, FINALITY_METADATA_KEY};
+    use crate::types::{KvLog, LogChunk};
     use ethers::types::{Address, H256};
     use parquet::basic::{Compression, Encoding, ZstdLevel};
     use parquet::data_type;
     use parquet::data_type::{ByteArrayType, Int32Type};
     use parquet::file::properties::{WriterProperties, WriterVersion};
+    use parquet::file::reader::{FileReader, SerializedFileReader};
+    use parquet::record::RowAccessor;
     use parquet::file::writer::SerializedFileWriter;
     use parquet::schema::parser::parse_message_type;
     use std::fs;
-    use std::path::Path;
     use std::sync::Arc;
 
     #[test]
     fn test_parquet() {
-        let path = Path::new("/tmp/sample.parquet");
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("sample.parquet");
 
         let message_type = "
           message schema {
@@ -325,6 +663,189 @@ mod test {
         writer.close().unwrap();
 
         let bytes = fs::read(&path).unwrap();
-        assert_eq!(&bytes[0..4], &[b'P', b'A', b'R', b'1']);
+        assert_eq!(&bytes[0..4], b"PAR1");
+    }
+
+    #[tokio::test]
+    async fn publish_result_on_success_only() {
+        let dir = tempfile::tempdir().unwrap();
+        let result_path = dir.path();
+        let metrics = Arc::new(Metrics::new());
+        let storage = ResultStorage::new(result_path.to_path_buf(), metrics.clone());
+        storage.check_writable().unwrap();
+        // liveness and readiness probes at once
+        std::thread::scope(|s| {
+            for _ in 0..4 {
+                s.spawn(|| storage.check_writable().unwrap());
+            }
+        });
+        assert_eq!(fs::read_dir(result_path).unwrap().count(), 0);
+
+        let (sender, worker) = storage
+            .open_writer(
+                "done".to_string(),
+                storage.result_file("done"),
+                WriterOptions {
+                    finality: Finality::Finalized,
+                    ..Default::default()
+                },
+            )
+            .await;
+        let log = KvLog {
+            address: Address::default(),
+            topics: vec![H256::default()],
+            data: None,
+        };
+        sender.send(Some(LogChunk { txs: vec![(1, 0, log)] })).await.unwrap();
+        assert!(!storage.result_file("done").exists());
+        sender.send(None).await.unwrap();
+        worker.await.unwrap().unwrap();
+        let bytes = fs::read(storage.result_file("done")).unwrap();
+        assert_eq!(&bytes[bytes.len() - 4..], b"PAR1");
+        assert_eq!(metrics.bytes_written.get(), bytes.len() as u64);
+        assert_eq!(metrics.chunk_write_seconds.get_sample_count(), 1);
+        let reader = SerializedFileReader::new(fs::File::open(storage.result_file("done")).unwrap()).unwrap();
+        let metadata = reader.metadata().file_metadata().key_value_metadata().unwrap();
+        let finality = metadata.iter().find(|kv| kv.key == FINALITY_METADATA_KEY).unwrap();
+        assert_eq!(finality.value.as_deref(), Some("finalized"));
+
+        // sender dropped without end of job
+        let (sender, worker) = storage
+            .open_writer("aborted".to_string(), storage.result_file("aborted"), WriterOptions::default())
+            .await;
+        drop(sender);
+        assert!(worker.await.unwrap().is_err());
+        assert!(!storage.result_file("aborted").exists());
+        assert!(!ResultStorage::tmp_file(&storage.result_file("aborted")).exists());
+        assert!(!storage.is_active("aborted"));
+
+        // follow job parts deleted with result
+        for part in [storage.part_file("follow", 0), storage.part_file("follow", 1), storage.part_file("follow_2", 0)] {
+            fs::write(part, b"PAR1").unwrap();
+        }
+        assert_eq!(
+            storage.part_file("follow", 1),
+            result_path.join("follow.part-000001.parquet")
+        );
+        assert!(storage.delete_result("follow").unwrap());
+        assert!(!storage.part_file("follow", 0).exists());
+        assert!(storage.part_file("follow_2", 0).exists());
+        assert!(!storage.delete_result("follow").unwrap());
+    }
+
+    #[tokio::test]
+    async fn read_back_missing_topics() {
+        let dir = tempfile::tempdir().unwrap();
+        let storage = ResultStorage::new(dir.path().to_path_buf(), Arc::new(Metrics::new()));
+        let log = |topics: u64, data: Option<u64>| KvLog {
+            address: Address::from_low_u64_be(1),
+            topics: (1..=topics).map(H256::from_low_u64_be).collect(),
+            data: data.map(|d| H256::from_low_u64_be(d).as_bytes().to_vec().into()),
+        };
+        let result_file = storage.result_file("topics");
+        let (sender, worker) = storage
+            .open_writer("topics".to_string(), result_file.clone(), WriterOptions::default())
+            .await;
+        let txs = vec![(1, 0, log(0, Some(7))), (1, 1, log(1, None)), (2, 0, log(4, Some(8))), (2, 1, log(2, None))];
+        sender.send(Some(LogChunk { txs })).await.unwrap();
+        sender.send(None).await.unwrap();
+        worker.await.unwrap().unwrap();
+
+        let reader = SerializedFileReader::new(fs::File::open(&result_file).unwrap()).unwrap();
+        let rows: Vec<(Vec<Option<H256>>, Option<H256>)> = reader
+            .get_row_iter(None)
+            .unwrap()
+            .map(|row| {
+                let row = row.unwrap();
+                let bytes = |i: usize| row.get_bytes(i).ok().map(|b| H256::from_slice(b.data()));
+                ((4..8).map(bytes).collect(), bytes(8))
+            })
+            .collect();
+        let topic = |n: u64| Some(H256::from_low_u64_be(n));
+        assert_eq!(
+            rows,
+            vec![
+                (vec![None, None, None, None], topic(7)),
+                (vec![topic(1), None, None, None], None),
+                (vec![topic(1), topic(2), topic(3), topic(4)], topic(8)),
+                (vec![topic(1), topic(2), None, None], None),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn write_event_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let result_path = dir.path();
+        let storage = ResultStorage::new(result_path.to_path_buf(), Arc::new(Metrics::new()));
+        let decoder = EventDecoder::new(&DecodeSpec {
+            abi: None,
+            events: vec![
+                "event Transfer(address indexed from, address indexed to, uint256 value)".to_string(),
+                "event Approval(address indexed owner, address indexed spender, uint256 value)".to_string(),
+            ],
+        })
+        .unwrap();
+        let transfer = KvLog {
+            address: Address::from_low_u64_be(10),
+            topics: vec![
+                decoder.events()[0].event.signature(),
+                H256::from_low_u64_be(1),
+                H256::from_low_u64_be(2),
+            ],
+            data: Some(H256::from_low_u64_be(500).as_bytes().to_vec().into()),
+        };
+        let other = KvLog {
+            address: Address::from_low_u64_be(10),
+            topics: vec![H256::from_low_u64_be(3)],
+            data: None,
+        };
+        let options = WriterOptions {
+            decoder: Some(Arc::new(decoder)),
+            ..Default::default()
+        };
+        let result_file = storage.result_file("decoded");
+        let (sender, worker) = storage.open_writer("decoded".to_string(), result_file.clone(), options).await;
+        sender
+            .send(Some(LogChunk {
+                txs: vec![(7, 0, other.clone()), (7, 1, transfer), (7, 2, other)],
+            }))
+            .await
+            .unwrap();
+        sender.send(None).await.unwrap();
+        worker.await.unwrap().unwrap();
+
+        let transfer_file = EventFiles::event_file(&result_file, "Transfer");
+        assert_eq!(transfer_file, result_path.join("decoded.Transfer.parquet"));
+        // no Approval logs
+        assert!(!EventFiles::event_file(&result_file, "Approval").exists());
+        let reader = SerializedFileReader::new(fs::File::open(&transfer_file).unwrap()).unwrap();
+        let columns: Vec<&str> = reader
+            .metadata()
+            .file_metadata()
+            .schema_descr()
+            .columns()
+            .iter()
+            .map(|c| c.name())
+            .collect();
+        assert_eq!(columns, vec!["block_n", "tx_n", "contract", "from", "to", "value"]);
+        let rows: Vec<_> = reader.get_row_iter(None).unwrap().collect();
+        assert_eq!(rows.len(), 1);
+        let row = rows[0].as_ref().unwrap();
+        assert_eq!(row.get_long(0).unwrap(), 7);
+        assert_eq!(row.get_bytes(4).unwrap().data(), Address::from_low_u64_be(2).as_bytes());
+        assert_eq!(row.get_bytes(5).unwrap().data(), H256::from_low_u64_be(500).as_bytes());
+        // raw logs with known event name
+        let reader = SerializedFileReader::new(fs::File::open(&result_file).unwrap()).unwrap();
+        let names: Vec<Option<String>> = reader
+            .get_row_iter(None)
+            .unwrap()
+            .map(|row| row.unwrap().get_string(9).ok().cloned())
+            .collect();
+        // unnamed log before named one
+        assert_eq!(names, vec![None, Some("ERC20.Transfer".to_string()), None]);
+
+        assert!(storage.delete_result("decoded").unwrap());
+        assert!(!transfer_file.exists());
     }
 }
@@ -0,0 +1,164 @@
+use crate::metrics::Metrics;
+use crate::storage::ResultStorage;
+use anyhow::Result;
+use ethers::types::U256;
+use parquet::data_type::{BoolType, ByteArray, ByteArrayType, Int32Type, Int64Type};
+use parquet::file::properties::WriterProperties;
+use parquet::file::writer::SerializedFileWriter;
+use parquet::schema::parser::parse_message_type;
+use std::collections::HashSet;
+use std::fs;
+use std::fs::File;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+// Values of one column in row group, same order as message type
+pub enum ColumnData {
+    Int64(Vec<i64>),
+    Int32(Vec<i32>),
+    Bytes(Vec<ByteArray>),
+    Bool(Vec<bool>),
+    // values of non-null rows, definition level of every row
+    OptionalInt32(Vec<i32>, Vec<i16>),
+    OptionalInt64(Vec<i64>, Vec<i16>),
+    // values of non-null rows, definition level of every row
+    OptionalBytes(Vec<ByteArray>, Vec<i16>),
+}
+
+impl ColumnData {
+    pub fn optional_int32(values: impl IntoIterator<Item = Option<i32>>) -> Self {
+        let (present, def_levels) = optional(values);
+        ColumnData::OptionalInt32(present, def_levels)
+    }
+
+    pub fn optional_int64(values: impl IntoIterator<Item = Option<i64>>) -> Self {
+        let (present, def_levels) = optional(values);
+        ColumnData::OptionalInt64(present, def_levels)
+    }
+
+    // Nullable column from row values
+    pub fn optional_bytes(values: impl IntoIterator<Item = Option<ByteArray>>) -> Self {
+        let (present, def_levels) = optional(values);
+        ColumnData::OptionalBytes(present, def_levels)
+    }
+}
+
+// 32 bytes big-endian
+pub fn uint256_value(value: U256) -> ByteArray {
+    let mut buf = [0u8; 32];
+    value.to_big_endian(&mut buf);
+    ByteArray::from(buf.to_vec())
+}
+
+// Non-null values and definition levels
+fn optional<T>(values: impl IntoIterator<Item = Option<T>>) -> (Vec<T>, Vec<i16>) {
+    let mut present = Vec::new();
+    let mut def_levels = Vec::new();
+    for value in values {
+        def_levels.push(value.is_some() as i16);
+        present.extend(value);
+    }
+    (present, def_levels)
+}
+
+// Result table written in row groups to temp file, renamed to result file on publish.
+// Job is active until writer published or dropped, unless file belongs to another writer of job
+pub struct TableWriter {
+    job_id: String,
+    result_file: PathBuf,
+    writer: Option<SerializedFileWriter<File>>,
+    active: Option<Arc<Mutex<HashSet<String>>>>,
+    metrics: Arc<Metrics>,
+}
+
+impl TableWriter {
+    pub(super) fn create(
+        job_id: &str,
+        result_file: &Path,
+        message_type: &str,
+        props: WriterProperties,
+        active: Option<Arc<Mutex<HashSet<String>>>>,
+        metrics: Arc<Metrics>,
+    ) -> Result<Self> {
+        let schema = Arc::new(parse_message_type(message_type)?);
+        let file = File::create(ResultStorage::tmp_file(result_file))?;
+        if let Some(active) = &active {
+            active.lock().unwrap().insert(job_id.to_string());
+        }
+        Ok(TableWriter {
+            job_id: job_id.to_string(),
+            result_file: result_file.to_path_buf(),
+            writer: Some(SerializedFileWriter::new(file, schema, props.into())?),
+            active,
+            metrics,
+        })
+    }
+
+    pub fn write_row_group(&mut self, columns: Vec<ColumnData>) -> Result<()> {
+        let writer = self.writer.as_mut().expect("writer not published");
+        let mut row_group_writer = writer.next_row_group()?;
+        for column in columns {
+            let mut col_writer = row_group_writer.next_column()?.unwrap();
+            match column {
+                ColumnData::Int64(values) => {
+                    col_writer.typed::<Int64Type>().write_batch(&values, None, None)?;
+                }
+                ColumnData::Int32(values) => {
+                    col_writer.typed::<Int32Type>().write_batch(&values, None, None)?;
+                }
+                ColumnData::Bytes(values) => {
+                    col_writer.typed::<ByteArrayType>().write_batch(&values, None, None)?;
+                }
+                ColumnData::Bool(values) => {
+                    col_writer.typed::<BoolType>().write_batch(&values, None, None)?;
+                }
+                ColumnData::OptionalInt32(values, def_levels) => {
+                    col_writer
+                        .typed::<Int32Type>()
+                        .write_batch(&values, Some(&def_levels), None)?;
+                }
+                ColumnData::OptionalInt64(values, def_levels) => {
+                    col_writer
+                        .typed::<Int64Type>()
+                        .write_batch(&values, Some(&def_levels), None)?;
+                }
+                ColumnData::OptionalBytes(values, def_levels) => {
+                    col_writer
+                        .typed::<ByteArrayType>()
+                        .write_batch(&values, Some(&def_levels), None)?;
+                }
+            }
+            col_writer.close()?;
+        }
+        row_group_writer.close()?;
+        Ok(())
+    }
+
+    // Close and rename to result file
+    pub fn publish(mut self) -> Result<()> {
+        let tmp_file = ResultStorage::tmp_file(&self.result_file);
+        let writer = self.writer.take().expect("writer not published");
+        let publish_result: Result<()> = (|| {
+            writer.close()?;
+            fs::rename(&tmp_file, &self.result_file)?;
+            Ok(())
+        })();
+        if publish_result.is_err() {
+            let _ = fs::remove_file(&tmp_file);
+        }
+        publish_result?;
+        self.metrics.bytes_written.inc_by(fs::metadata(&self.result_file)?.len());
+        Ok(())
+    }
+}
+
+impl Drop for TableWriter {
+    fn drop(&mut self) {
+        if self.writer.is_some() {
+            let _ = fs::remove_file(ResultStorage::tmp_file(&self.result_file));
+        }
+        if let Some(active) = &self.active {
+            active.lock().unwrap().remove(&self.job_id);
+        }
+    }
+}
@@ -19,6 +19,16 @@ pub struct KvLog {
     pub data: Option<Bytes>,
 }
 
+// Erigon receipt for storage, logs stored in TransactionLog
+#[derive(Debug, Clone, Deserialize)]
+pub struct KvReceipt {
+    pub tx_type: u8,
+    // state root of pre-Byzantium receipt
+    pub post_state: Option<Bytes>,
+    pub status: u64,
+    pub cumulative_gas_used: u64,
+}
+
 #[derive(Debug, Parser, Deserialize)]
 #[clap(author, version, about = "ErigonDbReader", long_about = None)]
 #[serde(rename_all = "kebab-case")]
@@ -31,4 +41,26 @@ pub struct AppCfg {
     pub db_path: String,
     #[clap(long, env)]
     pub result_path: String,
+    // jobs above limit wait in queue
+    #[clap(long, env, default_value_t = 2)]
+    pub max_concurrent_jobs: usize,
+    // delete completed results older than N seconds
+    #[clap(long, env)]
+    pub retention_max_age_secs: Option<u64>,
+    // keep total size of completed results below N bytes, oldest removed first
+    #[clap(long, env)]
+    pub retention_max_size_bytes: Option<u64>,
+    #[clap(long, env, default_value_t = 300)]
+    pub retention_sweep_interval_secs: u64,
+    // HMAC-SHA256 key of X-Signature-256 callback header
+    #[clap(long, env)]
+    pub webhook_secret: Option<String>,
+    // attempts after first failed callback, delay doubled from 1s
+    #[clap(long, env, default_value_t = 5)]
+    pub webhook_max_retries: u32,
+    #[clap(long, env, default_value_t = 10)]
+    pub webhook_timeout_secs: u64,
+    // follow jobs check for newly executed blocks every N seconds
+    #[clap(long, env, default_value_t = 5)]
+    pub follow_poll_interval_secs: u64,
 }
@@ -1,4 +1,6 @@
 use std::env;
+use std::time::{SystemTime, UNIX_EPOCH};
+
 pub fn setup_log() {
     if env::var_os("RUST_LOG").is_none() {
         // Set `RUST_LOG=debug` to see debug logs,
@@ -6,3 +8,10 @@ pub fn setup_log() {
     }
     env_logger::init();
 }
+
+pub fn unix_millis() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis() as u64
+}
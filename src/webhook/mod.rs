@@ -0,0 +1,267 @@
+use crate::job::{JobInfo, JobStatus};
+use crate::storage::ResultStorage;
+use crate::util::unix_millis;
+use anyhow::{bail, Result};
+use hmac::{Hmac, Mac};
+use log::{info, warn};
+use serde::Serialize;
+use sha2::Sha256;
+use std::time::Duration;
+use tokio::time::sleep;
+
+pub const SIGNATURE_HEADER: &str = "X-Signature-256";
+const INITIAL_BACKOFF: Duration = Duration::from_secs(1);
+
+// Body of job completion callback
+#[derive(Debug, Serialize)]
+pub struct JobNotification {
+    pub job_id: String,
+    pub status: &'static str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    // follow job writes part files instead
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub result_file: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub part_prefix: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub parts: Option<u32>,
+    pub rows: Option<u64>,
+    pub from_block: u64,
+    pub to_block: u64,
+    pub duration_ms: u64,
+}
+
+impl JobNotification {
+    pub fn new(job: &JobInfo) -> Self {
+        let error = match &job.status {
+            JobStatus::Failed(err) => Some(err.clone()),
+            _ => None,
+        };
+        let duration_ms = match (job.started_at, job.finished_at) {
+            (Some(started), Some(finished)) => finished.saturating_sub(started),
+            _ => 0,
+        };
+        JobNotification {
+            job_id: job.job_id.clone(),
+            status: job.status.name(),
+            error,
+            result_file: (!job.spec.follow).then(|| job.result_file.to_string_lossy().to_string()),
+            part_prefix: job
+                .spec
+                .follow
+                .then(|| ResultStorage::part_prefix(&job.result_file).to_string_lossy().to_string()),
+            parts: job.spec.follow.then_some(job.parts),
+            rows: job.rows,
+            from_block: job.spec.from_block,
+            to_block: job.to_block,
+            duration_ms,
+        }
+    }
+}
+
+// POST job result to callback url, retry with exponential backoff
+pub struct WebhookNotifier {
+    client: reqwest::Client,
+    // HMAC-SHA256 key of body signature, header not sent when not set
+    secret: Option<String>,
+    max_retries: u32,
+    backoff: Duration,
+}
+
+impl WebhookNotifier {
+    pub fn new(secret: Option<String>, max_retries: u32, timeout: Duration) -> Self {
+        info!("Webhook max retries:{} signed:{}", max_retries, secret.is_some());
+        let client = reqwest::Client::builder()
+            .timeout(timeout)
+            .build()
+            .expect("Err build http client");
+        WebhookNotifier {
+            client,
+            secret,
+            max_retries,
+            backoff: INITIAL_BACKOFF,
+        }
+    }
+
+    // Header value: t=<unix secs>,v1=<hex hmac of "<t>.<body>">, receiver rejects stale timestamps
+    pub fn sign(secret: &str, timestamp: u64, body: &[u8]) -> String {
+        let mut mac = Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC accepts any key size");
+        mac.update(format!("{}.", timestamp).as_bytes());
+        mac.update(body);
+        format!("t={},v1={}", timestamp, hex::encode(mac.finalize().into_bytes()))
+    }
+
+    pub async fn notify(&self, url: &str, job: &JobInfo) -> Result<()> {
+        let body = serde_json::to_vec(&JobNotification::new(job))?;
+        let mut backoff = self.backoff;
+        let mut attempt = 0;
+        loop {
+            match self.send(url, &body).await {
+                Ok(()) => {
+                    info!("[{}] Webhook sent", job.job_id);
+                    return Ok(());
+                }
+                Err(err) if attempt < self.max_retries => {
+                    attempt += 1;
+                    warn!(
+                        "[{}] Webhook err:{}. Retry {}/{} in {}mils",
+                        job.job_id,
+                        err,
+                        attempt,
+                        self.max_retries,
+                        backoff.as_millis()
+                    );
+                    sleep(backoff).await;
+                    backoff *= 2;
+                }
+                Err(err) => return Err(err),
+            }
+        }
+    }
+
+    async fn send(&self, url: &str, body: &[u8]) -> Result<()> {
+        let mut request = self
+            .client
+            .post(url)
+            .header(reqwest::header::CONTENT_TYPE, "application/json")
+            .body(body.to_vec());
+        if let Some(secret) = &self.secret {
+            // signed per attempt, retry carries fresh timestamp
+            request = request.header(SIGNATURE_HEADER, Self::sign(secret, unix_millis() / 1_000, body));
+        }
+        let status = request.send().await?.status();
+        if !status.is_success() {
+            bail!("Unexpected status {}", status);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::job::spec::{BlockTag, Finality, JobSpec};
+    use crate::job::{JobInfo, JobStatus};
+    use crate::util::unix_millis;
+    use crate::webhook::{JobNotification, WebhookNotifier};
+    use std::path::PathBuf;
+    use std::sync::{Arc, Mutex};
+    use std::time::Duration;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+
+    // Minimal HTTP server: answer statuses in order, record request heads and bodies
+    async fn stand_in(statuses: Vec<u16>) -> (String, Arc<Mutex<Vec<(String, String)>>>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/callback", listener.local_addr().unwrap());
+        let requests = Arc::new(Mutex::new(Vec::new()));
+        let recorded = requests.clone();
+        tokio::spawn(async move {
+            for status in statuses {
+                let (mut socket, _) = listener.accept().await.unwrap();
+                let mut buf = Vec::new();
+                let mut chunk = [0u8; 4096];
+                let (head, body) = loop {
+                    let n = socket.read(&mut chunk).await.unwrap();
+                    buf.extend_from_slice(&chunk[..n]);
+                    let text = String::from_utf8_lossy(&buf).to_string();
+                    if let Some((head, body)) = text.split_once("\r\n\r\n") {
+                        let len = head
+                            .lines()
+                            .find_map(|l| l.to_lowercase().strip_prefix("content-length: ").map(|v| v.to_string()))
+                            .map(|v| v.parse::<usize>().unwrap())
+                            .unwrap_or_default();
+                        if body.len() >= len {
+                            break (head.to_string(), body.to_string());
+                        }
+                    }
+                };
+                recorded.lock().unwrap().push((head, body));
+                let response = format!("HTTP/1.1 {} X\r\ncontent-length: 0\r\nconnection: close\r\n\r\n", status);
+                socket.write_all(response.as_bytes()).await.unwrap();
+            }
+        });
+        (url, requests)
+    }
+
+    fn job() -> JobInfo {
+        JobInfo {
+            job_id: "job_1".to_string(),
+            spec: JobSpec {
+                from_block: 10,
+                to_block: BlockTag::Number(20),
+                ..Default::default()
+            },
+            to_block: 20,
+            finality: Finality::Unfinalized,
+            result_file: PathBuf::from("/tmp/job_1.parquet"),
+            status: JobStatus::Completed,
+            created_at: 1_000,
+            started_at: Some(2_000),
+            finished_at: Some(2_500),
+            rows: Some(42),
+            last_block: None,
+            parts: 0,
+            block_hashes: Vec::new(),
+        }
+    }
+
+    #[tokio::test]
+    async fn notify_with_retries() {
+        let (url, requests) = stand_in(vec![500, 503, 200]).await;
+        let mut notifier = WebhookNotifier::new(Some("secret".to_string()), 3, Duration::from_secs(5));
+        notifier.backoff = Duration::from_millis(10);
+        notifier.notify(&url, &job()).await.unwrap();
+
+        let requests = requests.lock().unwrap();
+        assert_eq!(requests.len(), 3);
+        let (head, body) = &requests[2];
+        assert!(head.starts_with("POST /callback"));
+        let signature = head
+            .lines()
+            .find_map(|l| l.to_lowercase().strip_prefix("x-signature-256: ").map(|v| v.to_string()))
+            .unwrap();
+        let timestamp: u64 = signature.strip_prefix("t=").unwrap().split(',').next().unwrap().parse().unwrap();
+        assert!(timestamp.abs_diff(unix_millis() / 1_000) < 60);
+        assert_eq!(signature, WebhookNotifier::sign("secret", timestamp, body.as_bytes()));
+        // same body signed at other time differs
+        assert_ne!(signature, WebhookNotifier::sign("secret", timestamp - 1, body.as_bytes()));
+        let body: serde_json::Value = serde_json::from_str(body).unwrap();
+        assert_eq!(body["status"], "completed");
+        assert_eq!(body["rows"], 42);
+        assert_eq!(body["from_block"], 10);
+        assert_eq!(body["to_block"], 20);
+        assert_eq!(body["duration_ms"], 500);
+        assert_eq!(body["result_file"], "/tmp/job_1.parquet");
+        assert!(body.get("part_prefix").is_none());
+    }
+
+    #[tokio::test]
+    async fn notify_gives_up() {
+        let (url, requests) = stand_in(vec![500, 500]).await;
+        let mut notifier = WebhookNotifier::new(None, 1, Duration::from_secs(5));
+        notifier.backoff = Duration::from_millis(10);
+        let mut failed = job();
+        failed.status = JobStatus::Failed("Invalid db".to_string());
+        assert!(notifier.notify(&url, &failed).await.is_err());
+
+        let requests = requests.lock().unwrap();
+        assert_eq!(requests.len(), 2);
+        let (head, body) = &requests[0];
+        assert!(!head.to_lowercase().contains("x-signature-256"));
+        let body: serde_json::Value = serde_json::from_str(body).unwrap();
+        assert_eq!(body["status"], "failed");
+        assert_eq!(body["error"], "Invalid db");
+    }
+
+    #[test]
+    fn follow_job_parts() {
+        let mut follow = job();
+        follow.spec.follow = true;
+        follow.parts = 3;
+        let body = serde_json::to_value(JobNotification::new(&follow)).unwrap();
+        assert!(body.get("result_file").is_none());
+        assert_eq!(body["part_prefix"], "/tmp/job_1.part-");
+        assert_eq!(body["parts"], 3);
+    }
+}